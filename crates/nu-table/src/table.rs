@@ -14,6 +14,8 @@ pub struct Table {
     pub headers: Vec<StyledString>,
     pub data: Vec<Vec<StyledString>>,
     pub theme: Theme,
+    /// Rows shown after the data when drawn with `show_footer`, the headers again if empty
+    pub footer: Vec<Vec<StyledString>>,
 }
 
 #[derive(Debug, Clone)]
@@ -614,7 +616,17 @@ impl Theme {
 
 impl Table {
     pub fn new(headers: Vec<StyledString>, data: Vec<Vec<StyledString>>, theme: Theme) -> Table {
-        Table { headers, data, theme }
+        Table {
+            headers,
+            data,
+            theme,
+            footer: vec![],
+        }
+    }
+
+    pub fn with_footer(mut self, footer: Vec<Vec<StyledString>>) -> Table {
+        self.footer = footer;
+        self
     }
 }
 
@@ -623,6 +635,7 @@ pub struct ProcessedTable {
     pub headers: Vec<ProcessedCell>,
     pub data: Vec<Vec<ProcessedCell>>,
     pub theme: Theme,
+    pub footer: Vec<Vec<ProcessedCell>>,
 }
 
 #[derive(Debug)]
@@ -637,7 +650,7 @@ pub struct WrappedTable {
     pub headers: Vec<WrappedCell>,
     pub data: Vec<Vec<WrappedCell>>,
     pub theme: Theme,
-    pub footer: Vec<WrappedCell>,
+    pub footer: Vec<Vec<WrappedCell>>,
 }
 
 impl WrappedTable {
@@ -937,7 +950,9 @@ impl WrappedTable {
             }
 
             if !self.headers.is_empty() && !skip_headers {
-                output.push_str(&self.print_cell_contents(&self.footer, color_hm));
+                for row in &self.footer {
+                    output.push_str(&self.print_cell_contents(row, color_hm));
+                }
             }
         }
 
@@ -961,9 +976,9 @@ impl WrappedTable {
     }
 }
 
-fn process_table(table: &Table) -> ProcessedTable {
+fn process_rows(rows: &[Vec<StyledString>]) -> Vec<Vec<ProcessedCell>> {
     let mut processed_data = vec![];
-    for row in &table.data {
+    for row in rows {
         let mut out_row = vec![];
         for column in row {
             let cleaned = clean(&column.contents);
@@ -975,6 +990,12 @@ fn process_table(table: &Table) -> ProcessedTable {
         processed_data.push(out_row);
     }
 
+    processed_data
+}
+
+fn process_table(table: &Table) -> ProcessedTable {
+    let processed_data = process_rows(&table.data);
+
     let mut processed_headers = vec![];
     for header in &table.headers {
         let cleaned = clean(&header.contents);
@@ -988,6 +1009,7 @@ fn process_table(table: &Table) -> ProcessedTable {
         headers: processed_headers,
         data: processed_data,
         theme: table.theme.clone(),
+        footer: process_rows(&table.footer),
     }
 }
 
@@ -1004,7 +1026,7 @@ fn get_max_column_widths(processed_table: &ProcessedTable) -> Vec<usize> {
 
     max_num_columns = max(max_num_columns, processed_table.headers.len());
 
-    for row in &processed_table.data {
+    for row in processed_table.data.iter().chain(&processed_table.footer) {
         max_num_columns = max(max_num_columns, row.len());
     }
 
@@ -1014,7 +1036,7 @@ fn get_max_column_widths(processed_table: &ProcessedTable) -> Vec<usize> {
         output[column.0] = max(output[column.0], column_width(&column.1.contents));
     }
 
-    for row in &processed_table.data {
+    for row in processed_table.data.iter().chain(&processed_table.footer) {
         for column in row.iter().enumerate() {
             output[column.0] = max(output[column.0], column_width(&column.1.contents));
         }
@@ -1031,7 +1053,11 @@ pub fn maybe_truncate_columns(termwidth: usize, processed_table: &mut ProcessedT
     if max_num_of_columns < processed_table.headers.len() {
         processed_table.headers.truncate(max_num_of_columns);
 
-        for entry in processed_table.data.iter_mut() {
+        for entry in processed_table
+            .data
+            .iter_mut()
+            .chain(processed_table.footer.iter_mut())
+        {
             entry.truncate(max_num_of_columns);
         }
 
@@ -1043,7 +1069,11 @@ pub fn maybe_truncate_columns(termwidth: usize, processed_table: &mut ProcessedT
             style: TextStyle::basic_center(),
         });
 
-        for entry in processed_table.data.iter_mut() {
+        for entry in processed_table
+            .data
+            .iter_mut()
+            .chain(processed_table.footer.iter_mut())
+        {
             entry.push(ProcessedCell {
                 contents: vec![vec![Subline {
                     subline: "...".to_string(),
@@ -1168,45 +1198,47 @@ fn wrap_cells(
         output_headers.push(wrapped);
     }
 
-    let mut output_data = vec![];
-    for row in processed_table.data.into_iter() {
-        let mut output_row = vec![];
-        for column in row.into_iter().enumerate() {
-            let mut wrapped = WrappedCell {
-                lines: vec![],
-                max_width: 0,
-                style: column.1.style,
-            };
-            for contents in column.1.contents.into_iter() {
-                let (mut lines, inner_max_width) = wrap(
-                    max_column_width,
-                    contents.into_iter(),
-                    color_hm,
-                    re_leading,
-                    re_trailing,
-                );
-                wrapped.lines.append(&mut lines);
-                if inner_max_width > wrapped.max_width {
-                    wrapped.max_width = inner_max_width;
+    let mut wrap_rows = |rows: Vec<Vec<ProcessedCell>>| {
+        let mut output_data = vec![];
+        for row in rows.into_iter() {
+            let mut output_row = vec![];
+            for column in row.into_iter().enumerate() {
+                let mut wrapped = WrappedCell {
+                    lines: vec![],
+                    max_width: 0,
+                    style: column.1.style,
+                };
+                for contents in column.1.contents.into_iter() {
+                    let (mut lines, inner_max_width) = wrap(
+                        max_column_width,
+                        contents.into_iter(),
+                        color_hm,
+                        re_leading,
+                        re_trailing,
+                    );
+                    wrapped.lines.append(&mut lines);
+                    if inner_max_width > wrapped.max_width {
+                        wrapped.max_width = inner_max_width;
+                    }
                 }
+                if column_widths[column.0] < wrapped.max_width {
+                    column_widths[column.0] = wrapped.max_width;
+                }
+                output_row.push(wrapped);
             }
-            if column_widths[column.0] < wrapped.max_width {
-                column_widths[column.0] = wrapped.max_width;
-            }
-            output_row.push(wrapped);
+            output_data.push(output_row);
         }
-        output_data.push(output_row);
-    }
+        output_data
+    };
 
-    let mut footer = vec![
-        WrappedCell {
-            lines: vec![],
-            max_width: 0,
-            style: TextStyle { ..Default::default() },
-        };
-        output_headers.len()
-    ];
-    footer.clone_from_slice(&output_headers[..]);
+    let output_data = wrap_rows(processed_table.data);
+
+    // Without footer rows of its own, the table repeats its headers at the bottom
+    let footer = if processed_table.footer.is_empty() {
+        vec![output_headers.clone()]
+    } else {
+        wrap_rows(processed_table.footer)
+    };
 
     WrappedTable {
         column_widths,
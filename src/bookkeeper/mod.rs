@@ -1,182 +1,270 @@
-mod status;
+pub(crate) mod status;
 
-use std::{
-    io::{Read, Seek, Write},
-    path::PathBuf,
-};
-
-use fs_err as fs;
-use toml::value::{Table as TomlTable, Value as TomlValue};
+use bigdecimal::BigDecimal;
 
 use crate::{
-    error::{Error, Result, TomlTypeCheck, TomlTypeCheckDiagnosis},
+    config::{Config, OutputFormat},
+    error::{Error, Result},
+    file::BookkeepingFile,
     parser::Operation,
+    payee::PayeeIndex,
+    storage::{Access, Storage},
 };
 
-use status::BookkeeperStatus;
+pub use status::{BookkeeperStatus, SortKey, Sorting};
 
+/// Keeps the books of a single month, wherever they're stored
 pub struct Bookkeeper {
-    pub file: fs::File,
-    pub file_path: PathBuf,
-    pub file_contents: String,
-    pub table: TomlTable,
+    storage: Box<dyn Storage>,
+    month: BookkeepingFile,
     status: BookkeeperStatus,
+    /// Most that is meant to be spent in the month
+    target: Option<i64>,
 }
 
 impl Bookkeeper {
-    pub fn display_status(&self) {
-        // Safety: Always has file name because it's in format "MM-YYYY"
-        println!("Status for {:?}", self.file_path.file_name().unwrap());
-
-        self.status.display();
-    }
-
-    pub fn load_from_path(path: impl Into<PathBuf>) -> Result<Self> {
-        let path = path.into();
-        let mut file = fs::OpenOptions::new().read(true).write(true).open(&path)?;
-        let mut file_contents = String::new();
-        file.read_to_string(&mut file_contents)?;
-        file.rewind()?;
-
-        let table = Self::load_toml_table_or_default(&file_contents);
-
-        let type_check_diagnosis = type_check_toml_fields(&table);
-        if type_check_diagnosis.has_error_description() {
-            return Err(Error::InvalidTomlTypes {
-                description: type_check_diagnosis.into_inner(),
-                path,
-            });
+    pub fn display_status(&self, config: &Config, sorting: &Sorting) {
+        match config.output {
+            OutputFormat::Table => {
+                println!("Status for {:?}", self.month.as_str());
+                self.status.display(config, sorting);
+            }
+            OutputFormat::Json => println!("{:#}", self.status.to_json(&self.month, sorting)),
         }
+    }
 
-        let status = Self::status_from_toml_table(&table)?;
+    pub fn load(
+        mut storage: Box<dyn Storage>,
+        month: BookkeepingFile,
+        access: Access,
+    ) -> Result<Self> {
+        let data = storage.load_month(&month, access)?;
+        let status = BookkeeperStatus::from_operations(data.operations);
 
         Ok(Self {
-            file,
-            file_path: path,
-            file_contents,
-            table,
+            storage,
+            month,
             status,
+            target: data.target,
         })
     }
 
+    pub fn month(&self) -> &BookkeepingFile {
+        &self.month
+    }
+
+    pub fn status(&self) -> &BookkeeperStatus {
+        &self.status
+    }
+
+    pub fn target(&self) -> Option<i64> {
+        self.target
+    }
+
+    /// The descriptions used in every month of the books
+    pub fn payees(&mut self) -> Result<PayeeIndex> {
+        PayeeIndex::from_storage(self.storage.as_mut())
+    }
+
     pub fn add_operation(&mut self, operation: Operation) -> Result<()> {
-        let (array_key, kind_symbol) = operation.kind.name_and_symbol();
-
-        let line = format!(
-            "{d} {k} {a} {D}",
-            d = operation.day,
-            k = kind_symbol,
-            a = operation.amount,
-            D = operation.description
-        );
-
-        self.table[array_key]
-            .as_array_mut()
-            .unwrap()
-            .push(line.into());
-
-        let temporary_toml = TomlValue::Table(std::mem::take(&mut self.table));
-        let toml = toml::ser::to_string_pretty::<TomlValue>(&temporary_toml).unwrap();
-        self.table = unwrap_toml_table(temporary_toml);
-        write!(self.file, "{}", toml)?;
-        truncate_and_close_file(&mut self.file)?;
-        println!("Updated {}", self.file_path.display());
+        self.storage.append_operation(&self.month, &operation)?;
+
+        let mut operations = std::mem::take(&mut self.status.all_operations);
+        operations.push(operation);
+        self.status = BookkeeperStatus::from_operations(operations);
 
         Ok(())
     }
 
-    fn load_toml_table_or_default(input_text: &str) -> TomlTable {
-        let toml = if input_text.trim().is_empty() {
-            generate_default_toml()
-        } else {
-            input_text.parse().unwrap()
-        };
-
-        unwrap_toml_table(toml)
+    /// Replaces `expected`, the operation at `index` of the stored ones, with `operation`
+    ///
+    /// Fails if `expected` is no longer at `index`, e.g. because another porquinho
+    /// process changed the month since it was read.
+    pub fn replace_operation(
+        &mut self,
+        index: usize,
+        expected: &Operation,
+        operation: Operation,
+    ) -> Result<()> {
+        self.storage
+            .replace_operation(&self.month, index, expected, &operation)?;
+
+        self.reload()
     }
 
-    fn status_from_toml_table(table: &TomlTable) -> Result<BookkeeperStatus> {
-        BookkeeperStatus::from_toml_table(table)
+    /// Removes `expected`, the operation at `index` of the stored ones
+    ///
+    /// Fails if `expected` is no longer at `index`, e.g. because another porquinho
+    /// process changed the month since it was read.
+    pub fn remove_operation(&mut self, index: usize, expected: &Operation) -> Result<()> {
+        self.storage
+            .remove_operation(&self.month, index, expected)?;
+
+        self.reload()
     }
-}
 
-fn type_check_toml_fields(table: &TomlTable) -> TomlTypeCheckDiagnosis {
-    let is_take_array = table.get("take").map_or(false, TomlValue::is_array);
-    let is_put_array = table.get("put").map_or(false, TomlValue::is_array);
-    let is_target_int_or_undefined = table.get("target").map_or(true, TomlValue::is_integer);
-
-    let is_array_of_strings = |array_value: Option<&TomlValue>| {
-        array_value
-            .unwrap()
-            .as_array()
-            .unwrap()
-            .iter()
-            .all(|value| value.is_str())
-    };
+    pub fn set_target(&mut self, target: Option<i64>) -> Result<()> {
+        self.storage.set_target(&self.month, target)?;
+        self.target = target;
 
-    let is_take_array_of_strings = is_take_array && is_array_of_strings(table.get("take"));
-    let is_put_array_of_strings = is_put_array && is_array_of_strings(table.get("put"));
+        Ok(())
+    }
 
-    let toml_type_check = TomlTypeCheck {
-        is_take_array,
-        is_put_array,
-        is_target_int_or_undefined,
-        is_take_array_of_strings,
-        is_put_array_of_strings,
-    };
+    /// Fails with [`Error::BudgetExceeded`] if more was spent in the month than its target
+    pub fn check_target(&self) -> Result<()> {
+        match self.target {
+            Some(target) if self.status.take_total > BigDecimal::from(target) => {
+                Err(Error::BudgetExceeded {
+                    month: self.month.as_str().to_owned(),
+                    spent: self.status.take_total.clone(),
+                    target,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
 
-    toml_type_check.into_diagnosis()
-}
+    /// Reads the month again after changing it, since storages may keep its
+    /// operations in another order, e.g. TOML files keep them by kind
+    fn reload(&mut self) -> Result<()> {
+        let data = self.storage.load_month(&self.month, Access::ReadWrite)?;
+        self.status = BookkeeperStatus::from_operations(data.operations);
+        self.target = data.target;
 
-pub fn generate_default_toml() -> TomlValue {
-    toml::toml! {
-        take = []
-        put = []
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{io::Write, str::FromStr};
+    use std::str::FromStr;
 
     use bigdecimal::BigDecimal;
-    use tempfile::NamedTempFile;
 
     use super::*;
+    use crate::{
+        parser::OperationType,
+        storage::{MemoryStorage, Month},
+    };
+
+    fn october() -> BookkeepingFile {
+        BookkeepingFile::from_file_name("10-2024").unwrap()
+    }
+
+    fn operation(kind: OperationType, amount: &str) -> Operation {
+        Operation::new(22, kind, BigDecimal::from_str(amount).unwrap(), "Payment")
+    }
 
     #[test]
-    fn reads_income_and_outcome_total_from_file_correctly() {
-        let mut dummy = NamedTempFile::new().unwrap();
-
-        let toml = toml::toml! {
-            put = [
-                "22 + 200.50 Payment",
-                "22 + 300.25 Another Payment",
-            ]
-            take = [
-                "23 - 10.25 Lunch",
-                "23 - 10.27 Dinner",
-                "24 - 400.00 kindle-para-bish",
-            ]
+    fn reads_income_and_outcome_total_from_storage_correctly() {
+        let mut storage = MemoryStorage::new();
+        let month = Month {
+            operations: vec![
+                operation(OperationType::Deposit, "200.50"),
+                operation(OperationType::Deposit, "300.25"),
+                operation(OperationType::Withdraw, "10.25"),
+                operation(OperationType::Withdraw, "10.27"),
+                operation(OperationType::Withdraw, "400.00"),
+            ],
+            target: None,
         };
-        writeln!(dummy, "{}", toml).unwrap();
+        storage.write_month(&october(), &month).unwrap();
 
-        let bookkeeper = Bookkeeper::load_from_path(dummy.path()).unwrap();
+        let bookkeeper = Bookkeeper::load(Box::new(storage), october(), Access::ReadOnly).unwrap();
         let status = bookkeeper.status;
 
         assert_eq!(status.put_total, BigDecimal::from_str("500.75").unwrap());
         assert_eq!(status.take_total, BigDecimal::from_str("420.52").unwrap());
     }
-}
 
-fn truncate_and_close_file(file: &mut fs::File) -> Result<()> {
-    let written_len = file.stream_position()?;
-    file.set_len(written_len).map_err(Into::into)
-}
+    #[test]
+    fn checks_spending_against_the_target() {
+        let storage = Box::new(MemoryStorage::new());
+        let mut bookkeeper = Bookkeeper::load(storage, october(), Access::ReadWrite).unwrap();
+        bookkeeper
+            .add_operation(operation(OperationType::Withdraw, "300.50"))
+            .unwrap();
+        assert!(bookkeeper.check_target().is_ok());
+
+        bookkeeper.set_target(Some(301)).unwrap();
+        assert!(bookkeeper.check_target().is_ok());
+
+        bookkeeper.set_target(Some(300)).unwrap();
+        assert!(matches!(
+            bookkeeper.check_target(),
+            Err(Error::BudgetExceeded { target: 300, .. })
+        ));
+    }
+
+    #[test]
+    fn updates_totals_when_adding_operations() {
+        let storage = Box::new(MemoryStorage::new());
+        let mut bookkeeper = Bookkeeper::load(storage, october(), Access::ReadWrite).unwrap();
+
+        bookkeeper
+            .add_operation(operation(OperationType::Withdraw, "12"))
+            .unwrap();
+        bookkeeper
+            .add_operation(operation(OperationType::Deposit, "100"))
+            .unwrap();
+
+        assert_eq!(bookkeeper.status.take_total, BigDecimal::from(12));
+        assert_eq!(bookkeeper.status.put_total, BigDecimal::from(100));
+        assert_eq!(bookkeeper.status.all_operations.len(), 2);
+
+        let stored = bookkeeper
+            .storage
+            .load_month(&october(), Access::ReadOnly)
+            .unwrap();
+        assert_eq!(stored.operations, bookkeeper.status.all_operations);
+    }
+
+    #[test]
+    fn edits_and_removes_stored_operations() {
+        let storage = Box::new(MemoryStorage::new());
+        let mut bookkeeper = Bookkeeper::load(storage, october(), Access::ReadWrite).unwrap();
+
+        for amount in ["10", "20", "30"] {
+            bookkeeper
+                .add_operation(operation(OperationType::Withdraw, amount))
+                .unwrap();
+        }
+        bookkeeper.set_target(Some(1000)).unwrap();
+
+        let stored = bookkeeper.status.all_operations.clone();
+        bookkeeper
+            .replace_operation(0, &stored[0], operation(OperationType::Deposit, "15"))
+            .unwrap();
+        bookkeeper.remove_operation(1, &stored[1]).unwrap();
+
+        assert_eq!(bookkeeper.status.put_total, BigDecimal::from(15));
+        assert_eq!(bookkeeper.status.take_total, BigDecimal::from(30));
+
+        let stored = bookkeeper
+            .storage
+            .load_month(&october(), Access::ReadOnly)
+            .unwrap();
+        assert_eq!(stored.operations, bookkeeper.status.all_operations);
+        assert_eq!(stored.target, Some(1000));
+    }
+
+    #[test]
+    fn refuses_to_change_operations_that_moved() {
+        let storage = Box::new(MemoryStorage::new());
+        let mut bookkeeper = Bookkeeper::load(storage, october(), Access::ReadWrite).unwrap();
+
+        for amount in ["10", "20"] {
+            bookkeeper
+                .add_operation(operation(OperationType::Withdraw, amount))
+                .unwrap();
+        }
+        let first = bookkeeper.status.all_operations[0].clone();
+
+        let moved = bookkeeper.replace_operation(1, &first, operation(OperationType::Deposit, "5"));
+        assert!(matches!(moved, Err(Error::OperationChanged(_))));
 
-fn unwrap_toml_table(toml: TomlValue) -> TomlTable {
-    match toml {
-        TomlValue::Table(table) => table,
-        _ => unreachable!(),
+        let gone = bookkeeper.remove_operation(2, &first);
+        assert!(matches!(gone, Err(Error::OperationChanged(_))));
+        assert_eq!(bookkeeper.status.all_operations.len(), 2);
     }
 }
@@ -1,15 +1,15 @@
-use bigdecimal::BigDecimal;
-use nu_table::{draw_table, StyledString, Table, TextStyle, Theme};
-use std::collections::HashMap;
-use toml::value::Table as TomlTable;
-
 use crate::{
-    error::Result,
+    config::Config,
+    file::BookkeepingFile,
     parser::{Operation, OperationType},
 };
+use bigdecimal::BigDecimal;
+use nu_table::{draw_table, StyledString, Table, TextStyle};
+use serde_json::json;
+use std::{cmp::Ordering, collections::HashMap};
 
-#[allow(unused)]
-pub(super) struct BookkeeperStatus {
+/// The totals and operations of a month
+pub struct BookkeeperStatus {
     /// Total amount spent.
     pub take_total: BigDecimal,
     /// Total amount received.
@@ -22,15 +22,85 @@ pub(super) struct BookkeeperStatus {
     pub take_operations: Vec<Operation>,
 }
 
-fn table_row_from_operation(operation: &Operation) -> Vec<StyledString> {
-    let Operation { day, kind, amount, description } = operation;
+/// What the operations of a month can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Day,
+    Amount,
+    Description,
+    Kind,
+}
+
+impl SortKey {
+    /// Ties are broken by day, then kind
+    fn compare(self, a: &Operation, b: &Operation) -> Ordering {
+        let by_day = a.day.cmp(&b.day).then(a.kind.cmp(&b.kind));
+
+        match self {
+            Self::Day => by_day,
+            Self::Amount => a.amount.cmp(&b.amount).then(by_day),
+            Self::Description => a
+                .description
+                .to_lowercase()
+                .cmp(&b.description.to_lowercase())
+                .then(by_day),
+            Self::Kind => a.kind.cmp(&b.kind).then(a.day.cmp(&b.day)),
+        }
+    }
+}
+
+/// How the operations of a month are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sorting {
+    /// By day if not set, or by amount when only showing the `top` expenses
+    pub key: Option<SortKey>,
+    pub reverse: bool,
+    /// Only show this many of the largest expenses
+    pub top: Option<usize>,
+}
+
+impl Sorting {
+    /// Sorts `items` by the operation each of them has, keeping only the largest
+    /// expenses if `top` is set
+    pub fn apply<T>(&self, items: &mut Vec<T>, operation: impl Fn(&T) -> &Operation) {
+        if let Some(top) = self.top {
+            items.retain(|item| operation(item).kind == OperationType::Withdraw);
+            items.sort_by(|a, b| operation(b).amount.cmp(&operation(a).amount));
+            items.truncate(top);
+        }
+
+        // The largest expenses are shown first unless asked otherwise
+        let key = self.key.unwrap_or(match self.top {
+            Some(_) => SortKey::Amount,
+            None => SortKey::Day,
+        });
+        let is_descending = self.reverse != (self.key.is_none() && self.top.is_some());
+
+        items.sort_by(|a, b| {
+            let ordering = key.compare(operation(a), operation(b));
+            if is_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+}
+
+pub(crate) fn table_row_from_operation(
+    operation: &Operation,
+    decimals: usize,
+) -> Vec<StyledString> {
+    let Operation {
+        day, kind, amount, description, ..
+    } = operation;
 
     let (kind_name, _) = kind.name_and_symbol();
 
     let line: Vec<StyledString> = [
         format!("{day:2}"),
         kind_name.into(),
-        format!("{amount:8.2}"),
+        format!("{amount:8.decimals$}"),
         description.into(),
     ]
     .into_iter()
@@ -40,16 +110,29 @@ fn table_row_from_operation(operation: &Operation) -> Vec<StyledString> {
     line
 }
 
-fn table_header_from_column_names(column_names: &[&str]) -> Vec<StyledString> {
+pub(crate) fn table_header_from_column_names(column_names: &[&str]) -> Vec<StyledString> {
     column_names
         .iter()
         .map(|x| StyledString::new(x, TextStyle::default_header()))
         .collect()
 }
 
+/// `operation` as JSON, with the amount as a string so that no precision is lost
+pub(crate) fn operation_json(operation: &Operation) -> serde_json::Value {
+    let (kind, _) = operation.kind.name_and_symbol();
+
+    json!({
+        "day": operation.day,
+        "kind": kind,
+        "amount": operation.amount.to_string(),
+        "description": operation.description,
+        "category": operation.category,
+    })
+}
+
 impl BookkeeperStatus {
-    fn display_table(&self, table: &Table) {
-        let screen_width = 150;
+    fn display_table(&self, table: &Table, config: &Config) {
+        let screen_width = config.width;
 
         // Do not change any colors, yet.
         let colors = HashMap::new();
@@ -59,33 +142,32 @@ impl BookkeeperStatus {
         println!("{}", output);
     }
 
-    fn display_value_table(&self) {
+    pub(crate) fn display_value_table(&self, config: &Config) {
         let balance = &self.put_total - &self.take_total;
+        let Config { currency, decimals, .. } = config;
 
         let table = {
             let header = ["Incoming", "Outgoing", "Balance"];
             let header = table_header_from_column_names(&header);
 
             let rows = vec![
-                format!("R$ {:.2}", self.put_total),
-                format!("R$ {:.2}", self.take_total),
-                format!("R$ {:.2}", balance),
+                format!("{currency} {:.decimals$}", self.put_total),
+                format!("{currency} {:.decimals$}", self.take_total),
+                format!("{currency} {:.decimals$}", balance),
             ]
             .into_iter()
             .map(|x| StyledString::new(x, TextStyle::basic_left()))
             .collect();
 
-            let theme = Theme::compact();
-
-            Table::new(header, vec![rows], theme)
+            Table::new(header, vec![rows], config.table_theme())
         };
 
-        self.display_table(&table);
+        self.display_table(&table, config);
     }
 
-    fn display_operations_table(&self) {
-        let mut all_operations = self.all_operations.clone();
-        all_operations.sort_by(|a, b| a.day.cmp(&b.day).then(a.kind.cmp(&b.kind)));
+    fn display_operations_table(&self, config: &Config, sorting: &Sorting) {
+        let mut all_operations: Vec<_> = self.all_operations.iter().collect();
+        sorting.apply(&mut all_operations, |operation| operation);
 
         let table = {
             let header = ["day", "op", "amount", "description"];
@@ -93,53 +175,113 @@ impl BookkeeperStatus {
 
             let rows: Vec<Vec<StyledString>> = all_operations
                 .iter()
-                .map(table_row_from_operation)
+                .map(|operation| table_row_from_operation(operation, config.decimals))
                 .collect();
 
-            let theme = Theme::compact();
-
-            Table::new(header, rows, theme)
+            Table::new(header, rows, config.table_theme())
         };
 
-        self.display_table(&table);
+        self.display_table(&table, config);
     }
 
-    pub(super) fn display(&self) {
-        self.display_value_table();
-        self.display_operations_table();
+    pub(super) fn display(&self, config: &Config, sorting: &Sorting) {
+        self.display_value_table(config);
+        self.display_operations_table(config, sorting);
     }
 
-    pub(super) fn from_toml_table(table: &TomlTable) -> Result<Self> {
-        let (take, put) = (
-            table["take"].as_array().unwrap(),
-            table["put"].as_array().unwrap(),
-        );
-
-        let mut all_operations = vec![];
-        let mut put_operations = vec![];
-        let mut take_operations = vec![];
-
-        for operation in take.iter().chain(put) {
-            let operation = operation.as_str().unwrap();
-            let operation = Operation::from_str(operation).unwrap();
+    /// The status of `month` as JSON, with amounts as strings so that no precision is lost
+    pub fn to_json(&self, month: &BookkeepingFile, sorting: &Sorting) -> serde_json::Value {
+        let mut operations: Vec<_> = self.all_operations.iter().collect();
+        sorting.apply(&mut operations, |operation| operation);
+        let operations: Vec<_> = operations.into_iter().map(operation_json).collect();
 
-            all_operations.push(operation.clone());
+        json!({
+            "month": month.as_str(),
+            "incoming": self.put_total.to_string(),
+            "outgoing": self.take_total.to_string(),
+            "balance": (&self.put_total - &self.take_total).to_string(),
+            "operations": operations,
+        })
+    }
 
-            match operation.kind {
-                OperationType::Withdraw => take_operations.push(operation),
-                OperationType::Deposit => put_operations.push(operation),
-            }
-        }
+    pub fn from_operations(all_operations: Vec<Operation>) -> Self {
+        let (take_operations, put_operations): (Vec<_>, Vec<_>) = all_operations
+            .iter()
+            .cloned()
+            .partition(|operation| operation.kind == OperationType::Withdraw);
 
         let take_total: BigDecimal = take_operations.iter().map(|x| &x.amount).sum();
         let put_total: BigDecimal = put_operations.iter().map(|x| &x.amount).sum();
 
-        Ok(Self {
+        Self {
             take_total,
             put_total,
             all_operations,
             take_operations,
             put_operations,
-        })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use super::{SortKey, Sorting};
+    use crate::parser::{Operation, OperationType};
+
+    fn sorted(sorting: Sorting) -> Vec<String> {
+        let operations = [
+            (12, OperationType::Withdraw, 30, "uber"),
+            (5, OperationType::Deposit, 3000, "Salary"),
+            (3, OperationType::Withdraw, 900, "Rent"),
+            (12, OperationType::Withdraw, 12, "Lunch"),
+        ];
+        let mut operations: Vec<_> = operations
+            .into_iter()
+            .map(|(day, kind, amount, description)| {
+                Operation::new(day, kind, BigDecimal::from(amount), description)
+            })
+            .collect();
+
+        sorting.apply(&mut operations, |operation| operation);
+        operations.into_iter().map(|op| op.description).collect()
+    }
+
+    #[test]
+    fn sorts_operations() {
+        assert_eq!(
+            sorted(Sorting::default()),
+            ["Rent", "Salary", "uber", "Lunch"]
+        );
+
+        let by_amount = Sorting {
+            key: Some(SortKey::Amount),
+            reverse: true,
+            ..Sorting::default()
+        };
+        assert_eq!(sorted(by_amount), ["Salary", "Rent", "uber", "Lunch"]);
+
+        let by_description = Sorting {
+            key: Some(SortKey::Description),
+            ..Sorting::default()
+        };
+        assert_eq!(sorted(by_description), ["Lunch", "Rent", "Salary", "uber"]);
+    }
+
+    #[test]
+    fn keeps_only_the_largest_expenses() {
+        let top = Sorting {
+            top: Some(2),
+            ..Sorting::default()
+        };
+        assert_eq!(sorted(top), ["Rent", "uber"]);
+
+        let top_by_day = Sorting {
+            key: Some(SortKey::Day),
+            top: Some(2),
+            reverse: true,
+        };
+        assert_eq!(sorted(top_by_day), ["uber", "Rent"]);
     }
 }
@@ -0,0 +1,101 @@
+/// A change to a single line
+enum Change<'a> {
+    Kept,
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Renders the lines that differ between `old` and `new`, grouped in hunks
+/// headed by the line of `old` where they start:
+///
+/// ```text
+/// @@ line 3 @@
+/// -    "3 - 5.00 Bus ",
+/// +    "3 - 5.00 Bus",
+/// ```
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let mut output = String::new();
+    let mut old_line = 0;
+    let mut in_hunk = false;
+
+    for change in changes(&old, &new) {
+        match change {
+            Change::Kept => {
+                old_line += 1;
+                in_hunk = false;
+                continue;
+            }
+            _ if in_hunk => {}
+            _ => {
+                output.push_str(&format!("@@ line {} @@\n", old_line + 1));
+                in_hunk = true;
+            }
+        }
+
+        match change {
+            Change::Removed(line) => {
+                old_line += 1;
+                output.push_str(&format!("-{line}\n"));
+            }
+            Change::Added(line) => output.push_str(&format!("+{line}\n")),
+            Change::Kept => unreachable!(),
+        }
+    }
+
+    output
+}
+
+/// The shortest list of changes that turn `old` into `new`, found through
+/// their longest common subsequence of lines
+fn changes<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Change<'a>> {
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0_usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = vec![];
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            changes.push(Change::Kept);
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            changes.push(Change::Removed(old[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(new[j]));
+            j += 1;
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::line_diff;
+
+    #[test]
+    fn shows_only_the_changed_lines() {
+        let old = "take = [\n  \"3 - 5 Bus \",\n  \"1 - 2 Gum\",\n]\nput = []\n";
+        let new = "take = [\n  \"1 - 2 Gum\",\n  \"3 - 5 Bus\",\n]\nput = []\ntarget = 10\n";
+
+        assert_eq!(
+            line_diff(old, new),
+            "@@ line 2 @@\n-  \"3 - 5 Bus \",\n@@ line 4 @@\n+  \"3 - 5 Bus\",\n@@ line 6 @@\n+target = 10\n"
+        );
+        assert_eq!(line_diff(old, old), "");
+    }
+}
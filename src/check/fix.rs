@@ -0,0 +1,179 @@
+use std::path::Path;
+
+use toml_edit::{Document, Item, Table, Value};
+
+use super::diff::line_diff;
+use crate::{
+    diagnostic::SourceFile,
+    lock::{FileLock, LOCK_TIMEOUT},
+    migration,
+    parser::OperationType,
+    vault::Vault,
+    Error, Result,
+};
+
+/// Repairs the fixable problems of the bookkeeping file at `path`, keeping the
+/// previous version as a backup
+///
+/// Returns a diff of what changed, if anything did.
+pub fn fix_file(path: &Path, vault: &Vault) -> Result<Option<String>> {
+    let _lock = FileLock::acquire(path, LOCK_TIMEOUT)?;
+    let original = vault.read_to_string(path)?;
+    let source = SourceFile::new(path, &original);
+
+    // Files in newer formats might not follow the rules of this one
+    if migration::format_version(&source).is_err() {
+        return Ok(None);
+    }
+
+    let contents = if migration::is_outdated(&source)? {
+        match migration::migrate_file(&source, vault) {
+            Ok(migrated) => migrated,
+            // Operations that can't be upgraded are reported by the checks
+            Err(Error::Diagnostic(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        }
+    } else {
+        original.clone()
+    };
+
+    match fix_contents(&contents) {
+        Some(fixed) if fixed != contents => vault.write_atomically(path, &fixed)?,
+        _ if contents != original => {}
+        _ => return Ok(None),
+    }
+
+    let fixed = vault.read_to_string(path)?;
+    Ok(Some(line_diff(&original, &fixed)))
+}
+
+/// Repairs the problems of a bookkeeping file that have a single obvious solution,
+/// keeping the comments and formatting of the rest of the file:
+///
+/// * whitespace around descriptions is removed
+/// * operations are sorted by day, along with the comments above them
+///
+/// Returns `None` if the file isn't valid TOML, since there's nothing safe to do then.
+pub fn fix_contents(contents: &str) -> Option<String> {
+    let mut document: Document = contents.parse().ok()?;
+
+    for kind in [OperationType::Withdraw, OperationType::Deposit] {
+        let (array_key, _) = kind.name_and_symbol();
+
+        let records = match document
+            .get_mut(array_key)
+            .and_then(Item::as_array_of_tables_mut)
+        {
+            Some(records) => records,
+            None => continue,
+        };
+
+        records.iter_mut().for_each(trim_description);
+
+        // Only sort when every operation has a day to sort by
+        let days: Option<Vec<i64>> = records
+            .iter()
+            .map(|record| record.get("day").and_then(Item::as_integer))
+            .collect();
+
+        if let Some(days) = days {
+            // Records are laid out by their position in the document, which is kept
+            // in place so that records of other kinds don't move around
+            let positions: Vec<Option<usize>> = records.iter().map(Table::position).collect();
+
+            let mut sorted: Vec<(i64, Table)> =
+                days.into_iter().zip(records.iter().cloned()).collect();
+            sorted.sort_by_key(|(day, _)| *day);
+
+            records.clear();
+            for ((_, mut record), position) in sorted.into_iter().zip(positions) {
+                if let Some(position) = position {
+                    record.set_position(position);
+                }
+                records.push(record);
+            }
+        }
+    }
+
+    Some(document.to_string())
+}
+
+fn trim_description(record: &mut Table) {
+    let description = match record.get_mut("description").and_then(Item::as_value_mut) {
+        Some(description) => description,
+        None => return,
+    };
+
+    if let Some(text) = description.as_str().filter(|text| *text != text.trim()) {
+        let decor = description.decor().clone();
+
+        *description = Value::from(text.trim());
+        *description.decor_mut() = decor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fix_contents;
+    use crate::check::check_file;
+    use crate::{diagnostic::SourceFile, file::BookkeepingFile};
+
+    #[test]
+    fn trims_and_sorts_operations_keeping_their_comments() {
+        let contents = r#"format_version = 2
+
+[[take]]
+day = 12
+amount = "30.00"
+description = "Market"
+
+# with Ana
+[[take]]
+day = 3
+amount = "12.00"
+description = "Lunch " # at work
+
+[[put]]
+day = 1
+amount = "3000"
+description = "Salary"
+"#;
+
+        let expected = r#"format_version = 2
+
+# with Ana
+[[take]]
+day = 3
+amount = "12.00"
+description = "Lunch" # at work
+
+[[take]]
+day = 12
+amount = "30.00"
+description = "Market"
+
+[[put]]
+day = 1
+amount = "3000"
+description = "Salary"
+"#;
+
+        assert_eq!(fix_contents(contents).unwrap(), expected);
+    }
+
+    #[test]
+    fn fixed_files_have_no_fixable_problems_left() {
+        let contents = "format_version = 2\ntarget = 10\n\n[[take]]\nday = 4\namount = 1\ndescription = \" Bus\"\n\n[[take]]\nday = 2\namount = 1\ndescription = \"Gum\"\n";
+
+        let fixed = fix_contents(contents).unwrap();
+        let file = BookkeepingFile::from_file_name("10-2024").unwrap();
+        let problems = check_file(&SourceFile::new("10-2024".as_ref(), &fixed), &file);
+
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+
+    #[test]
+    fn leaves_unparseable_files_alone() {
+        assert!(fix_contents("take = [").is_none());
+    }
+}
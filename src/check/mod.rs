@@ -0,0 +1,503 @@
+mod diff;
+mod fix;
+
+use std::{
+    collections::HashMap,
+    ops::Not,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use bigdecimal::BigDecimal;
+use toml::value::{Table as TomlTable, Value as TomlValue};
+
+use crate::{
+    diagnostic::{Diagnostic, SourceFile},
+    file::BookkeepingFile,
+    migration::{self, CURRENT_FORMAT_VERSION, FORMAT_VERSION_KEY},
+    parser::{Operation, OperationType, RECORD_FIELDS},
+    storage::{type_check_toml_fields, Storage, TomlDirectory},
+    vault::Vault,
+    Error, Result,
+};
+
+/// The only keys a bookkeeping file may have
+const KNOWN_KEYS: [&str; 4] = ["take", "put", "target", FORMAT_VERSION_KEY];
+
+/// Suggestion given for problems that `check --fix` repairs
+const FIX_HINT: &str = "run `porquinho check --fix` to repair it";
+
+/// The outcome of checking the bookkeeping files of a folder
+pub struct CheckReport {
+    pub files_checked: usize,
+    pub problems: Vec<Diagnostic>,
+    /// Files repaired while checking, along with a diff of what changed in each
+    pub fixes: Vec<(PathBuf, String)>,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn display(&self) {
+        for (path, diff) in &self.fixes {
+            println!("Fixed {}\n{diff}", path.display());
+        }
+
+        for problem in &self.problems {
+            println!("{problem}\n");
+        }
+
+        println!(
+            "Checked {} file(s), found {} problem(s)",
+            self.files_checked,
+            self.problems.len()
+        );
+    }
+}
+
+/// Checks every bookkeeping file in `folder`, repairing the problems that
+/// can be fixed automatically first if `fix` is set
+///
+/// Files whose names are not in the `MM-YYYY` format, such as the backups
+/// and locks kept next to bookkeeping files, are ignored.
+pub fn check_folder(folder: &Path, fix: bool, vault: &Vault) -> Result<CheckReport> {
+    let storage = TomlDirectory::new(folder, vault.clone());
+    let months = storage.list_months()?;
+
+    let mut problems = vec![];
+    let mut fixes = vec![];
+    for month in &months {
+        let path = storage.path_of(month);
+
+        if fix {
+            if let Some(diff) = fix::fix_file(&path, vault)? {
+                fixes.push((path.clone(), diff));
+            }
+        }
+
+        let contents = vault.read_to_string(&path)?;
+        problems.extend(check_file(&SourceFile::new(&path, &contents), month));
+    }
+
+    Ok(CheckReport {
+        files_checked: months.len(),
+        problems,
+        fixes,
+    })
+}
+
+/// Checks the contents of a single bookkeeping file
+pub fn check_file(source: &SourceFile, file: &BookkeepingFile) -> Vec<Diagnostic> {
+    let mut problems = find_problems(source, file);
+    // Shown in the order they appear in the file
+    problems.sort_by_key(|problem| (problem.line, problem.column));
+
+    problems
+}
+
+fn find_problems(source: &SourceFile, file: &BookkeepingFile) -> Vec<Diagnostic> {
+    let mut problems = vec![];
+
+    if source.contents.trim().is_empty() {
+        // Empty files are filled in once the first operation is recorded
+        return problems;
+    }
+
+    let table = match toml::from_str::<TomlTable>(source.contents) {
+        Ok(table) => table,
+        Err(err) => {
+            problems.push(source.diagnose_toml(&err));
+            return problems;
+        }
+    };
+
+    // Outdated files are upgraded by `--fix`, while newer ones can't be understood
+    let outdated_version = match migration::format_version(source) {
+        Ok(Some(version)) if version < CURRENT_FORMAT_VERSION => Some(version),
+        Ok(_) => None,
+        Err(Error::Diagnostic(diagnostic)) => {
+            problems.push(*diagnostic);
+            return problems;
+        }
+        Err(err) => {
+            problems.push(source.diagnose_key(
+                FORMAT_VERSION_KEY,
+                err.to_string(),
+                "files written by newer versions of porquinho can't be checked",
+            ));
+            return problems;
+        }
+    };
+
+    let mut type_check = type_check_toml_fields(&table);
+    if outdated_version.is_some() {
+        // Outdated files keep their operations as strings until they're upgraded
+        type_check.is_take_array_of_tables = true;
+        type_check.is_put_array_of_tables = true;
+    }
+    let failures = type_check.failures();
+    for (key, failure) in &failures {
+        let hint = match *key {
+            "target" => "the target is the most to spend in the month, e.g. `target = 3000`",
+            _ => "operations are stored in [[take]] and [[put]] tables",
+        };
+        problems.push(source.diagnose_key(key, *failure, hint));
+    }
+
+    for key in table.keys() {
+        if KNOWN_KEYS.contains(&key.as_str()).not() {
+            problems.push(source.diagnose_key(
+                key,
+                format!("unknown key '{key}'"),
+                "bookkeeping files only have the keys 'take', 'put', 'target' and 'format_version'",
+            ));
+        }
+    }
+
+    if let Some(version) = outdated_version {
+        check_placement(source, &table, &mut problems);
+        problems.push(source.diagnose_key(
+            FORMAT_VERSION_KEY,
+            format!(
+                "file is in version {version} of the bookkeeping format, \
+                 the current one is {CURRENT_FORMAT_VERSION}"
+            ),
+            FIX_HINT,
+        ));
+        // Anything else that would stop the upgrade must be fixed by hand first,
+        // values of unexpected types being reported above
+        if failures.is_empty() {
+            if let Err(Error::Diagnostic(diagnostic)) = migration::migrate(source, version) {
+                problems.push(*diagnostic);
+            }
+        }
+        return problems;
+    }
+
+    for kind in [OperationType::Withdraw, OperationType::Deposit] {
+        let (array_key, _) = kind.name_and_symbol();
+
+        if let Some(records) = table.get(array_key).and_then(TomlValue::as_array) {
+            check_operations(source, file, &kind, records, &mut problems);
+        }
+    }
+
+    problems
+}
+
+/// Points out the operations of outdated files that are stored in the array of the
+/// other kind, which upgrading moves to the array their sign belongs to
+fn check_placement(source: &SourceFile, table: &TomlTable, problems: &mut Vec<Diagnostic>) {
+    // How many times each operation was seen, to point at the right one when repeated
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+
+    for kind in [OperationType::Withdraw, OperationType::Deposit] {
+        let (array_key, _) = kind.name_and_symbol();
+        let lines = table
+            .get(array_key)
+            .and_then(TomlValue::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(TomlValue::as_str);
+
+        for line in lines {
+            let seen_before = seen.entry(line).or_default();
+            let occurrence = *seen_before;
+            *seen_before += 1;
+
+            // Operations that can't be parsed stop the upgrade, which reports them
+            let operation = match Operation::from_str(line) {
+                Ok(operation) => operation,
+                Err(_) => continue,
+            };
+            if operation.kind != kind {
+                let (right_array_key, _) = operation.kind.name_and_symbol();
+                problems.push(source.diagnose_string(
+                    line,
+                    occurrence,
+                    format!("'{line}' is in the '{array_key}' array"),
+                    &format!("move it to the '{right_array_key}' array, or {FIX_HINT}"),
+                ));
+            }
+        }
+    }
+}
+
+/// Checks the records of the array of operations of the given `kind`
+fn check_operations(
+    source: &SourceFile,
+    file: &BookkeepingFile,
+    kind: &OperationType,
+    records: &[TomlValue],
+    problems: &mut Vec<Diagnostic>,
+) {
+    let (array_key, _) = kind.name_and_symbol();
+
+    // Days of the operations in the order they are stored, along with their index
+    let mut days = vec![];
+
+    for (index, value) in records.iter().enumerate() {
+        let record = match value.as_table() {
+            Some(record) => record,
+            // Reported by the type checks
+            None => continue,
+        };
+
+        let mut diagnose = |needle: &str, message: String, hint: &str| {
+            problems.push(source.diagnose_in_record(array_key, index, needle, message, hint));
+        };
+
+        if records[..index].contains(value) {
+            diagnose(
+                "",
+                "duplicated operation".into(),
+                "remove one of them if the operation was recorded twice by mistake",
+            );
+        }
+
+        for field in record.keys() {
+            if RECORD_FIELDS.contains(&field.as_str()).not() {
+                diagnose(
+                    field,
+                    format!("unknown field '{field}' in operation"),
+                    "operations only have the fields 'day', 'amount', 'description', \
+                     'time', 'category', 'currency' and 'id'",
+                );
+            }
+        }
+
+        let description = record.get("description").and_then(TomlValue::as_str);
+        if description.is_some_and(|text| text != text.trim()) {
+            diagnose(
+                "description",
+                "description has leading or trailing whitespace".into(),
+                FIX_HINT,
+            );
+        }
+
+        let operation = match Operation::from_record(kind.clone(), record) {
+            Ok(operation) => operation,
+            Err(err) => {
+                diagnose(err.offending_text().trim(), err.to_string(), err.hint());
+                continue;
+            }
+        };
+
+        if operation.amount < BigDecimal::from(0) {
+            diagnose(
+                "amount",
+                format!("negative amount in the '{array_key}' array"),
+                "amounts are always positive, the array of an operation tells where the money went",
+            );
+        }
+
+        if file.has_day(operation.day).not() {
+            diagnose(
+                "day",
+                format!("day {} does not exist in {}", operation.day, file.as_str()),
+                "the day must exist in the month of the file",
+            );
+        }
+
+        days.push((index, operation.day));
+    }
+
+    let unsorted = days.windows(2).find(|pair| pair[0].1 > pair[1].1);
+    if let Some([_, (index, _)]) = unsorted {
+        problems.push(source.diagnose_in_record(
+            array_key,
+            *index,
+            "",
+            format!("operations of the '{array_key}' array are not sorted by day"),
+            FIX_HINT,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Not, path::Path};
+
+    use super::{check_file, check_folder};
+    use crate::{diagnostic::SourceFile, file::BookkeepingFile, vault::Vault};
+
+    fn problems_of(contents: &str) -> Vec<String> {
+        let file = BookkeepingFile::from_file_name("02-2023").unwrap();
+        let source = SourceFile::new(Path::new("02-2023"), contents);
+
+        check_file(&source, &file)
+            .into_iter()
+            .map(|problem| problem.message)
+            .collect()
+    }
+
+    /// A file in the current format with the given records, written as `(kind, day, amount, description)`
+    fn month_with(records: &[(&str, u8, &str, &str)]) -> String {
+        let mut contents = String::from("format_version = 2\n");
+        for (kind, day, amount, description) in records {
+            contents.push_str(&format!(
+                "\n[[{kind}]]\nday = {day}\namount = \"{amount}\"\ndescription = \"{description}\"\n"
+            ));
+        }
+
+        contents
+    }
+
+    #[test]
+    fn accepts_well_formed_files() {
+        let contents = month_with(&[("take", 3, "12.00", "Lunch"), ("put", 1, "3000", "Salary")]);
+        assert!(problems_of(&format!("target = 1000\n{contents}")).is_empty());
+        assert!(problems_of("format_version = 2\n").is_empty());
+    }
+
+    #[test]
+    fn flags_invalid_days_duplicates_and_unknown_keys_and_fields() {
+        let contents = month_with(&[
+            ("take", 3, "12.00", "Lunch"),
+            ("take", 3, "12.00", "Lunch"),
+            ("take", 29, "5.00", "Bus"),
+            ("put", 1, "-3000", "Salary"),
+        ]);
+        let contents = format!("budget = 2000\n{contents}tip = \"10%\"\n");
+
+        assert_eq!(
+            problems_of(&contents),
+            [
+                "unknown key 'budget'",
+                "duplicated operation",
+                "day 29 does not exist in 02-2023",
+                "negative amount in the 'put' array",
+                "unknown field 'tip' in operation",
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_fixable_problems() {
+        let contents = month_with(&[("take", 4, "1.00", "Bus"), ("take", 2, "12.00", "Lunch ")]);
+
+        assert_eq!(
+            problems_of(&contents),
+            [
+                "operations of the 'take' array are not sorted by day",
+                "description has leading or trailing whitespace",
+            ]
+        );
+    }
+
+    #[test]
+    fn fixes_files_before_checking_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("02-2023");
+        std::fs::write(
+            &path,
+            "take = [\"4 - 1 Bus\", \"2 + 9 Gift\", \"3 - 2 Gum\"]\n",
+        )
+        .unwrap();
+
+        let report = check_folder(dir.path(), true, &Vault::default()).unwrap();
+
+        assert!(report.is_ok(), "{:?}", report.problems);
+        assert_eq!(report.fixes.len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "format_version = 2\n\n[[take]]\nday = 3\namount = \"2\"\ndescription = \"Gum\"\n\n\
+             [[take]]\nday = 4\namount = \"1\"\ndescription = \"Bus\"\n\n\
+             [[put]]\nday = 2\namount = \"9\"\ndescription = \"Gift\"\n"
+        );
+        assert!(dir.path().join("02-2023.v0.bak").exists());
+    }
+
+    #[test]
+    fn flags_outdated_files_and_files_in_newer_formats() {
+        assert_eq!(
+            problems_of("format_version = 1\ntake = []\nput = []\n"),
+            ["file is in version 1 of the bookkeeping format, the current one is 2"]
+        );
+        assert_eq!(
+            problems_of("take = [\"3 - 1,00 Lunch\"]\nput = []\n"),
+            [
+                "file is in version 0 of the bookkeeping format, the current one is 2",
+                "'1,00' could not be parsed as a decimal",
+            ]
+        );
+        assert_eq!(problems_of("format_version = 999\n").len(), 1);
+    }
+
+    #[test]
+    fn flags_misplaced_operations_of_outdated_files() {
+        assert_eq!(
+            problems_of(
+                "format_version = 1\ntake = [\"1 + 3000 Salary\"]\nput = [\"2 - 5 Bus\"]\n"
+            ),
+            [
+                "file is in version 1 of the bookkeeping format, the current one is 2",
+                "'1 + 3000 Salary' is in the 'take' array",
+                "'2 - 5 Bus' is in the 'put' array",
+            ]
+        );
+        assert_eq!(
+            problems_of("target = \"lots\"\ntake = [\"1 + 3000 Salary\"]\n"),
+            [
+                "target is not a integer",
+                "file is in version 0 of the bookkeeping format, the current one is 2",
+                "'1 + 3000 Salary' is in the 'take' array",
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_malformed_files() {
+        assert_eq!(
+            problems_of(&month_with(&[("take", 3, "1,00", "Lunch")])),
+            ["'1,00' could not be parsed as a decimal"]
+        );
+
+        assert_eq!(
+            problems_of("format_version = 2\nput = [\"1 + 3000 Salary\"]\n"),
+            ["put array contains an element that is not an operation record"]
+        );
+
+        assert_eq!(problems_of("take = [\n").len(), 1);
+    }
+
+    #[test]
+    fn points_at_the_keys_of_unexpected_types() {
+        let file = BookkeepingFile::from_file_name("02-2023").unwrap();
+        let contents = "format_version = 2\ntake = []\ntarget = \"lots\"\nput = 3\n";
+        let source = SourceFile::new(Path::new("02-2023"), contents);
+
+        let problems: Vec<_> = check_file(&source, &file)
+            .into_iter()
+            .map(|problem| (problem.line, problem.message))
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                (3, "target is not a integer".to_owned()),
+                (4, "put is not an array".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_checks_bookkeeping_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("01-2023"), "format_version = 2\n").unwrap();
+        std::fs::write(
+            dir.path().join("02-2023"),
+            month_with(&[("take", 30, "1", "Bus")]),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("02-2023.bak"), "not toml").unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Ledger").unwrap();
+
+        let report = check_folder(dir.path(), false, &Vault::default()).unwrap();
+
+        assert_eq!(report.files_checked, 2);
+        assert_eq!(report.problems.len(), 1);
+        assert!(report.is_ok().not());
+    }
+}
@@ -1,5 +1,18 @@
+use std::path::PathBuf;
+
 use bigdecimal::BigDecimal;
-use clap::Parser;
+use clap::{ArgEnum, Args, Parser};
+use clap_complete::Shell;
+
+use porquinho::{
+    bookkeeper::SortKey,
+    file::BookkeepingFile,
+    is_valid_profile_name,
+    parser::OperationType,
+    search::{AmountRange, DayRange, Filter, Pattern},
+    storage::Backend,
+    Result as PorquinhoResult,
+};
 
 /// Simplistic personal finances helper
 ///
@@ -7,6 +20,26 @@ use clap::Parser;
 #[derive(Parser, Debug)]
 #[clap(about, version)]
 pub struct Opts {
+    /// Record every change to your books as a commit of a git repository in their folder
+    #[clap(long, global = true, env = "PORQUINHO_GIT")]
+    pub git: bool,
+
+    /// Only print the results of commands and errors
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print more details about what porquinho is doing, repeat for even more
+    #[clap(short, long, global = true, parse(from_occurrences))]
+    pub verbose: u8,
+
+    /// Keep the books in this folder instead of the default one
+    #[clap(long, global = true, env = "PORQUINHO_DATA_DIR", parse(from_os_str))]
+    pub data_dir: Option<PathBuf>,
+
+    /// Use a separate set of books and settings, e.g. `--profile household`
+    #[clap(long, global = true, env = "PORQUINHO_PROFILE", parse(try_from_str = parse_profile))]
+    pub profile: Option<String>,
+
     #[clap(subcommand)]
     pub cmd: Subcommand,
 }
@@ -14,21 +47,402 @@ pub struct Opts {
 #[derive(Parser, PartialEq, Eq, Debug)]
 pub enum Subcommand {
     /// Record a new withdraw from your account
+    ///
+    /// Without an amount and description, they're asked for one at a time in a terminal,
+    /// along with the day and category, suggesting what's already in your books.
     Take {
-        #[clap(required = true)]
-        amount: BigDecimal,
+        #[clap(requires = "description")]
+        amount: Option<BigDecimal>,
+
+        description: Option<String>,
 
-        #[clap(required = true)]
-        description: String,
+        /// One of the categories in your settings, if any are set
+        #[clap(short, long)]
+        category: Option<String>,
     },
     /// Record a new deposit to your account
+    ///
+    /// Without an amount and description, they're asked for one at a time in a terminal,
+    /// along with the day and category, suggesting what's already in your books.
     Put {
-        #[clap(required = true)]
-        amount: BigDecimal,
+        #[clap(requires = "description")]
+        amount: Option<BigDecimal>,
 
-        #[clap(required = true)]
-        description: String,
+        description: Option<String>,
+
+        /// One of the categories in your settings, if any are set
+        #[clap(short, long)]
+        category: Option<String>,
     },
     /// Current status for your account
-    Status,
+    ///
+    /// With any filter, only the matching operations of the month and their totals
+    /// are shown.
+    Status {
+        /// Only operations whose description contains this text, ignoring case
+        #[clap(long)]
+        filter: Option<String>,
+
+        #[clap(flatten)]
+        criteria: FilterArgs,
+
+        /// Sort the operations by this instead of by day
+        #[clap(long, arg_enum)]
+        sort: Option<SortBy>,
+
+        /// Sort the operations the other way around
+        #[clap(long)]
+        reverse: bool,
+
+        /// Only show the N largest expenses, largest first unless sorted otherwise
+        #[clap(long, value_name = "N")]
+        top: Option<usize>,
+
+        /// Exit with code 3 if more was spent in the month than its target
+        #[clap(long)]
+        check_target: bool,
+    },
+    /// Look for operations across your books, showing their totals
+    ///
+    /// E.g. `porquinho search uber --amount '>20' --from 01-2026`
+    Search {
+        /// Text the descriptions must contain, ignoring case
+        pattern: Option<String>,
+
+        #[clap(flatten)]
+        criteria: FilterArgs,
+
+        /// Only search from this month on, e.g. 01-2026
+        #[clap(long, parse(try_from_str = parse_month))]
+        from: Option<BookkeepingFile>,
+
+        /// Only search up to this month, e.g. 12-2026
+        #[clap(long, parse(try_from_str = parse_month))]
+        to: Option<BookkeepingFile>,
+    },
+    /// Look for problems in all of your bookkeeping files
+    ///
+    /// Exits with a non-zero code if any problem is found.
+    Check {
+        /// Repair the problems that have an obvious fix, showing what changed
+        #[clap(long)]
+        fix: bool,
+    },
+    /// Answer a question about your books, written in a small query language
+    ///
+    /// E.g. `porquinho query "take where category = food and month >= 2026-01 group by
+    /// month sum amount"`. See the README for everything a query can have.
+    Query { query: String },
+    /// Sum up your books over a period of time
+    Report {
+        #[clap(subcommand)]
+        period: ReportPeriod,
+    },
+    /// Move your books to another kind of storage
+    ///
+    /// What's left in the previous storage is kept with a `.migrated` extension.
+    Migrate {
+        /// The storage to move the books to
+        #[clap(long, arg_enum)]
+        to: BackendArg,
+    },
+    /// Encrypt your books with a passphrase
+    ///
+    /// The passphrase is asked for whenever the books are used, unless it's set in
+    /// the PORQUINHO_PASSPHRASE environment variable. Needs porquinho to be built
+    /// with the `encryption` feature.
+    Encrypt,
+    /// Decrypt your books for good
+    Decrypt,
+    /// See and change your settings, kept in the config folder of the profile
+    Config {
+        #[clap(subcommand)]
+        action: ConfigAction,
+    },
+    /// Show the history of changes to your books, newest first
+    ///
+    /// The history is recorded by running porquinho with `--git`.
+    Log {
+        /// Only show changes to this month, e.g. 10-2026
+        #[clap(parse(try_from_str = parse_month))]
+        month: Option<BookkeepingFile>,
+
+        /// Show at most this many changes
+        #[clap(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// List the descriptions in your books, most used first, along with their other spellings
+    ///
+    /// New descriptions that look like one of these are pointed out by `take` and
+    /// `put`, or recorded with its usual spelling if `normalize_descriptions` is set.
+    Payees,
+    /// Print a script that completes porquinho's commands in your shell
+    ///
+    /// In bash, zsh and fish, descriptions, categories and profiles are completed
+    /// from your books and settings as well.
+    Completions {
+        #[clap(arg_enum)]
+        shell: Shell,
+    },
+    /// Print the manual page of porquinho, e.g. `porquinho man > porquinho.1`
+    ///
+    /// Each command has a page of its own as well, e.g. `porquinho man config set >
+    /// porquinho-config-set.1`.
+    Man {
+        /// The command to print the page of, instead of the one of porquinho
+        command: Vec<String>,
+    },
+    /// Browse, add, edit and delete operations in a full-screen interface
+    ///
+    /// Only available when porquinho is built with `--features tui`.
+    Tui,
+    /// Print the values the shell completions offer, one per line
+    #[clap(hide = true)]
+    Complete {
+        #[clap(arg_enum)]
+        kind: CompletionKind,
+    },
+}
+
+/// What operations must be like to be shown by `search` and `status`
+#[derive(Args, PartialEq, Eq, Debug)]
+pub struct FilterArgs {
+    /// Take the description text as a regular expression
+    #[clap(long)]
+    pub regex: bool,
+
+    /// Only amounts in this range, e.g. '>100', '<=50', 10..20, 10.. or 42
+    #[clap(long, parse(try_from_str = parse_amounts))]
+    pub amount: Option<AmountRange>,
+
+    /// Only operations of this kind
+    #[clap(long, arg_enum)]
+    pub kind: Option<Kind>,
+
+    /// Only days in this range, e.g. 1..15, 20.., ..10 or 5
+    #[clap(long, parse(try_from_str = parse_days))]
+    pub day: Option<DayRange>,
+
+    /// Only operations in this category
+    #[clap(short, long)]
+    pub category: Option<String>,
+
+    /// Only descriptions with this tag, e.g. `--tag work` for "Dinner #work"
+    #[clap(long)]
+    pub tag: Option<String>,
+}
+
+impl FilterArgs {
+    /// The filter these criteria make, along with `description` as the text descriptions must have
+    pub fn to_filter(&self, description: Option<&str>) -> PorquinhoResult<Filter> {
+        Ok(Filter {
+            description: description
+                .map(|text| Pattern::new(text, self.regex))
+                .transpose()?,
+            amount: self.amount.clone(),
+            kind: self.kind.map(Into::into),
+            days: self.day,
+            category: self.category.clone(),
+            tag: self.tag.clone(),
+        })
+    }
+}
+
+/// The kinds of storage `migrate` moves the books to
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BackendArg {
+    /// A TOML file per month, the default
+    Toml,
+    /// A single SQLite database, if porquinho was built with the `sqlite` feature
+    Sqlite,
+}
+
+impl From<BackendArg> for Backend {
+    fn from(backend: BackendArg) -> Self {
+        match backend {
+            BackendArg::Toml => Self::Toml,
+            BackendArg::Sqlite => Self::Sqlite,
+        }
+    }
+}
+
+/// The kinds of operations, named after the subcommands that add them
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Take,
+    Put,
+}
+
+impl From<Kind> for OperationType {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Take => Self::Withdraw,
+            Kind::Put => Self::Deposit,
+        }
+    }
+}
+
+/// What `status --sort` sorts operations by
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SortBy {
+    Day,
+    Amount,
+    Description,
+    Kind,
+}
+
+impl From<SortBy> for SortKey {
+    fn from(sort: SortBy) -> Self {
+        match sort {
+            SortBy::Day => Self::Day,
+            SortBy::Amount => Self::Amount,
+            SortBy::Description => Self::Description,
+            SortBy::Kind => Self::Kind,
+        }
+    }
+}
+
+/// The periods of time that `report` sums up
+#[derive(Parser, PartialEq, Eq, Debug)]
+pub enum ReportPeriod {
+    /// Every month of a year, with its income, expenses, balance and target, and
+    /// the totals and averages of the year
+    Year {
+        /// E.g. 2026
+        #[clap(parse(try_from_str = parse_year))]
+        year: i32,
+    },
+}
+
+/// What the shell completions complete from the books and settings
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompletionKind {
+    Descriptions,
+    Categories,
+    Profiles,
+}
+
+#[derive(Parser, PartialEq, Eq, Debug)]
+pub enum ConfigAction {
+    /// Show the value of a setting
+    Get { key: String },
+    /// Change a setting, e.g. `porquinho config set categories food,transport`
+    Set { key: String, value: String },
+    /// Show every setting
+    List,
+}
+
+impl Subcommand {
+    /// Whether running this subcommand changes the bookkeeping files
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            Self::Take { .. }
+                | Self::Put { .. }
+                | Self::Check { fix: true }
+                | Self::Migrate { .. }
+                | Self::Encrypt
+                | Self::Decrypt
+        )
+    }
+
+    /// Describes the change made to the books by this subcommand, for their history,
+    /// e.g. `take 42.10 Lunch (10-2026)`
+    ///
+    /// `changes` are what was stored by the subcommands that take their operations from
+    /// the user, after they were asked for and their descriptions spelled as usual.
+    pub fn change_summary(&self, changes: &[String]) -> Option<String> {
+        match self {
+            Self::Take { .. } | Self::Put { .. } => changes.first().cloned(),
+            Self::Tui => match changes {
+                [] => None,
+                [change] => Some(change.clone()),
+                changes => Some(format!(
+                    "tui: {} changes\n\n{}",
+                    changes.len(),
+                    changes.join("\n")
+                )),
+            },
+            Self::Check { fix: true } => Some("check --fix".into()),
+            Self::Migrate { to } => {
+                // Safety: no variant of `BackendArg` is skipped by clap
+                let backend = to.to_possible_value().unwrap();
+                Some(format!("migrate --to {}", backend.get_name()))
+            }
+            Self::Encrypt => Some("encrypt".into()),
+            Self::Decrypt => Some("decrypt".into()),
+            Self::Status { .. }
+            | Self::Search { .. }
+            | Self::Query { .. }
+            | Self::Report { .. }
+            | Self::Check { fix: false }
+            | Self::Config { .. }
+            | Self::Log { .. }
+            | Self::Payees
+            | Self::Completions { .. }
+            | Self::Man { .. }
+            | Self::Complete { .. } => None,
+        }
+    }
+}
+
+fn parse_month(text: &str) -> Result<BookkeepingFile, String> {
+    BookkeepingFile::from_file_name(text)
+        .ok_or_else(|| format!("'{text}' is not a month in the MM-YYYY format"))
+}
+
+fn parse_year(text: &str) -> Result<i32, String> {
+    text.parse()
+        .ok()
+        .filter(|year| (1000..=9999).contains(year))
+        .ok_or_else(|| format!("'{text}' is not a year in the YYYY format"))
+}
+
+fn parse_amounts(text: &str) -> Result<AmountRange, String> {
+    AmountRange::parse(text).ok_or_else(|| {
+        format!("'{text}' is not a range of amounts, e.g. '>100', '<=50', 10..20, 10.. or 42")
+    })
+}
+
+fn parse_days(text: &str) -> Result<DayRange, String> {
+    DayRange::parse(text)
+        .ok_or_else(|| format!("'{text}' is not a range of days, e.g. 1..15, 20.., ..10 or 5"))
+}
+
+fn parse_profile(text: &str) -> Result<String, String> {
+    if is_valid_profile_name(text) {
+        Ok(text.to_owned())
+    } else {
+        Err(format!(
+            "'{text}' is not a valid profile name, use only letters, digits, '-' and '_'"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Subcommand;
+
+    #[test]
+    fn summarizes_what_was_stored() {
+        let take = Subcommand::Take {
+            amount: None,
+            description: None,
+            category: None,
+        };
+        let lunch = "take 42.10 Lunch (10-2026)".to_owned();
+        assert_eq!(
+            take.change_summary(std::slice::from_ref(&lunch)),
+            Some(lunch.clone())
+        );
+        // Nothing was added when the questions were left unanswered
+        assert_eq!(take.change_summary(&[]), None);
+
+        let target = "target 3000 (10-2026)".to_owned();
+        assert_eq!(Subcommand::Tui.change_summary(&[]), None);
+        assert_eq!(
+            Subcommand::Tui.change_summary(&[lunch.clone(), target]),
+            Some("tui: 2 changes\n\ntake 42.10 Lunch (10-2026)\ntarget 3000 (10-2026)".into())
+        );
+    }
 }
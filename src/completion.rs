@@ -0,0 +1,98 @@
+use std::{collections::BTreeSet, path::Path};
+
+use crate::{
+    config::Config,
+    payee::PayeeIndex,
+    storage,
+    vault::{self, Vault},
+    Result,
+};
+
+/// Descriptions and categories already used in the books, offered by the shell
+/// completions of `take` and `put`
+#[derive(Debug, Default)]
+pub struct Candidates {
+    pub descriptions: BTreeSet<String>,
+    pub categories: BTreeSet<String>,
+}
+
+impl Candidates {
+    /// Everything used in the latest months of the books in `folder`, along with the
+    /// categories in `config`
+    ///
+    /// Encrypted books are left out, since completing a word is no reason to
+    /// ask for the passphrase.
+    pub fn gather(folder: &Path, config: &Config) -> Result<Self> {
+        let mut candidates = if vault::is_encrypted(folder) {
+            Self::default()
+        } else {
+            let mut storage = storage::open(folder, Vault::unlock(folder)?)?;
+            Self::from_index(&PayeeIndex::from_recent_months(
+                storage.as_mut(),
+                PayeeIndex::RECENT_MONTHS,
+            )?)
+        };
+
+        candidates
+            .categories
+            .extend(config.categories.iter().cloned());
+
+        Ok(candidates)
+    }
+
+    /// Every spelling of the payees in `index`, along with the categories they were last given
+    pub fn from_index(index: &PayeeIndex) -> Self {
+        let payees = index.payees();
+
+        Self {
+            descriptions: payees
+                .iter()
+                .flat_map(|payee| payee.spellings())
+                .map(str::to_owned)
+                .collect(),
+            categories: index.categories().into_iter().map(str::to_owned).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use super::Candidates;
+    use crate::{
+        file::BookkeepingFile,
+        parser::{Operation, OperationType},
+        payee::PayeeIndex,
+        storage::{MemoryStorage, Storage},
+    };
+
+    #[test]
+    fn gathers_descriptions_and_categories_of_every_month() {
+        let mut storage = MemoryStorage::new();
+
+        let mut lunch = Operation::new(3, OperationType::Withdraw, BigDecimal::from(12), "Lunch");
+        lunch.category = Some("food".into());
+        let salary = Operation::new(5, OperationType::Deposit, BigDecimal::from(900), "Salary");
+
+        for (month, operation) in [
+            ("09-2026", &lunch),
+            ("10-2026", &salary),
+            ("10-2026", &lunch),
+        ] {
+            let month = BookkeepingFile::from_file_name(month).unwrap();
+            storage.append_operation(&month, operation).unwrap();
+        }
+
+        let index = PayeeIndex::from_storage(&mut storage).unwrap();
+        let candidates = Candidates::from_index(&index);
+        assert_eq!(
+            candidates.descriptions.into_iter().collect::<Vec<_>>(),
+            ["Lunch", "Salary"]
+        );
+        assert_eq!(
+            candidates.categories.into_iter().collect::<Vec<_>>(),
+            ["food"]
+        );
+    }
+}
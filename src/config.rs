@@ -0,0 +1,411 @@
+use std::{fmt::Write, ops::Not, path::Path};
+
+use fs_err as fs;
+use nu_table::Theme;
+use toml::value::{Table as TomlTable, Value as TomlValue};
+use toml_edit::{Array, Document};
+
+use crate::{diagnostic::SourceFile, dirs, file::write_atomically, Error, Result};
+
+/// Name of the file kept in the config folder of each profile
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Every setting, along with the kind of value it takes
+const SETTINGS: [(&str, Kind); 8] = [
+    ("default_profile", Kind::Text),
+    ("currency", Kind::Text),
+    ("decimals", Kind::Number),
+    ("theme", Kind::Text),
+    ("width", Kind::Number),
+    ("categories", Kind::List),
+    ("output", Kind::Text),
+    ("normalize_descriptions", Kind::Bool),
+];
+
+const THEMES: [&str; 10] = [
+    "basic",
+    "thin",
+    "light",
+    "compact",
+    "with_love",
+    "compact_double",
+    "rounded",
+    "reinforced",
+    "heavy",
+    "none",
+];
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Text,
+    Number,
+    Bool,
+    /// Given as comma separated values on the command line
+    List,
+}
+
+/// How command results are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+impl OutputFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "table" => Some(Self::Table),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Table => "table",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// The settings of a profile, kept in its `config.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Profile used when `--profile` isn't given, only read from the default profile
+    pub default_profile: Option<String>,
+    /// Shown before the totals
+    pub currency: String,
+    /// Decimal places shown in amounts
+    pub decimals: usize,
+    /// One of the themes of `nu_table`
+    pub theme: String,
+    /// Width of the terminal that tables are fit into
+    pub width: usize,
+    /// Categories that operations may be given, any category is accepted if empty
+    pub categories: Vec<String>,
+    pub output: OutputFormat,
+    /// Whether descriptions that look like one already in the books are
+    /// recorded with its usual spelling instead
+    pub normalize_descriptions: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_profile: None,
+            currency: "R$".into(),
+            decimals: 2,
+            theme: "compact".into(),
+            width: 150,
+            categories: vec![],
+            output: OutputFormat::Table,
+            normalize_descriptions: false,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the settings kept in `config_folder`, which are all defaults if there's no config file
+    pub fn load(config_folder: &Path) -> Result<Self> {
+        let path = config_folder.join(CONFIG_FILE_NAME);
+        if path.exists().not() {
+            return Ok(Self::default());
+        }
+
+        log::debug!("loading settings from {}", path.display());
+        let contents = fs::read_to_string(&path)?;
+        let source = SourceFile::new(&path, &contents);
+        let table: TomlTable =
+            toml::from_str(&contents).map_err(|err| source.diagnose_toml(&err))?;
+
+        let mut config = Self::default();
+        for (key, value) in &table {
+            config
+                .apply(key, value)
+                .map_err(|message| invalid_setting(&path, key, message))?;
+        }
+
+        Ok(config)
+    }
+
+    /// The value of the setting `key`, written the way `config set` takes it
+    pub fn get(&self, config_folder: &Path, key: &str) -> Result<String> {
+        let value = match self.value_of(key) {
+            Some(value) => value,
+            None if setting_kind(key).is_some() => return Ok(String::new()),
+            None => return Err(unknown_setting(config_folder, key)),
+        };
+
+        let text = match value {
+            TomlValue::String(text) => text,
+            TomlValue::Array(values) => values
+                .iter()
+                .filter_map(TomlValue::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+            other => other.to_string(),
+        };
+
+        Ok(text)
+    }
+
+    /// Every setting that has a value, one `key = value` per line
+    pub fn list(&self) -> String {
+        let mut listing = String::new();
+
+        for (key, _) in SETTINGS {
+            if let Some(value) = self.value_of(key) {
+                // Safety: writing to a String never fails
+                writeln!(listing, "{key} = {value}").unwrap();
+            }
+        }
+
+        listing
+    }
+
+    /// Changes the setting `key` in the config file in `config_folder` to `text`,
+    /// keeping the comments and formatting of the rest of the file
+    pub fn set(config_folder: &Path, key: &str, text: &str) -> Result<()> {
+        let path = config_folder.join(CONFIG_FILE_NAME);
+        let kind = setting_kind(key).ok_or_else(|| unknown_setting(config_folder, key))?;
+
+        let value = match kind {
+            Kind::Text => TomlValue::String(text.into()),
+            Kind::Number => text
+                .parse()
+                .map(TomlValue::Integer)
+                .unwrap_or_else(|_| TomlValue::String(text.into())),
+            Kind::Bool => text
+                .parse()
+                .map(TomlValue::Boolean)
+                .unwrap_or_else(|_| TomlValue::String(text.into())),
+            Kind::List => TomlValue::Array(
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|item| item.is_empty().not())
+                    .map(|item| TomlValue::String(item.into()))
+                    .collect(),
+            ),
+        };
+
+        // Only the new value is checked, so that a broken file can be repaired
+        Self::default()
+            .apply(key, &value)
+            .map_err(|message| invalid_setting(&path, key, message))?;
+
+        let contents = if path.exists() {
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+        let mut document: Document = contents.parse()?;
+
+        document[key] = match value {
+            TomlValue::String(text) => toml_edit::value(text),
+            TomlValue::Integer(number) => toml_edit::value(number),
+            TomlValue::Boolean(flag) => toml_edit::value(flag),
+            TomlValue::Array(values) => {
+                let array: Array = values.iter().filter_map(TomlValue::as_str).collect();
+                toml_edit::value(array)
+            }
+            _ => unreachable!(),
+        };
+
+        write_atomically(&path, document.to_string().as_bytes())
+    }
+
+    /// The style of the tables
+    pub fn table_theme(&self) -> Theme {
+        match self.theme.as_str() {
+            "basic" => Theme::basic(),
+            "thin" => Theme::thin(),
+            "light" => Theme::light(),
+            "with_love" => Theme::with_love(),
+            "compact_double" => Theme::compact_double(),
+            "rounded" => Theme::rounded(),
+            "reinforced" => Theme::reinforced(),
+            "heavy" => Theme::heavy(),
+            "none" => Theme::none(),
+            _ => Theme::compact(),
+        }
+    }
+
+    /// Makes sure `category` is one of the configured categories, if any are
+    pub fn check_category(&self, category: &str) -> Result<()> {
+        if self.categories.is_empty() || self.categories.iter().any(|known| known == category) {
+            return Ok(());
+        }
+
+        Err(Error::UnknownCategory {
+            category: category.into(),
+            known: self.categories.join(", "),
+        })
+    }
+
+    /// Changes the setting `key` to `value`, describing what's wrong with it if invalid
+    fn apply(&mut self, key: &str, value: &TomlValue) -> Result<(), String> {
+        match key {
+            "default_profile" => {
+                let profile = text(value)?;
+                if dirs::is_valid_profile_name(profile).not() {
+                    return Err("use only letters, digits, '-' and '_'".into());
+                }
+                self.default_profile = Some(profile.into());
+            }
+            "currency" => self.currency = text(value)?.into(),
+            "decimals" => self.decimals = number(value, 0, 10)?,
+            "theme" => {
+                let theme = text(value)?;
+                if THEMES.contains(&theme).not() {
+                    return Err(format!("expected one of {}", THEMES.join(", ")));
+                }
+                self.theme = theme.into();
+            }
+            "width" => self.width = number(value, 20, 1000)?,
+            "categories" => {
+                let values = value.as_array().ok_or("expected a list of names")?;
+                self.categories = values
+                    .iter()
+                    .map(|value| text(value).map(Into::into))
+                    .collect::<Result<_, _>>()?;
+            }
+            "output" => {
+                self.output =
+                    OutputFormat::from_name(text(value)?).ok_or("expected either table or json")?;
+            }
+            "normalize_descriptions" => {
+                self.normalize_descriptions = value.as_bool().ok_or("expected true or false")?;
+            }
+            _ => return Err(no_such_setting()),
+        }
+
+        Ok(())
+    }
+
+    fn value_of(&self, key: &str) -> Option<TomlValue> {
+        let value = match key {
+            "default_profile" => TomlValue::String(self.default_profile.clone()?),
+            "currency" => TomlValue::String(self.currency.clone()),
+            "decimals" => TomlValue::Integer(self.decimals as i64),
+            "theme" => TomlValue::String(self.theme.clone()),
+            "width" => TomlValue::Integer(self.width as i64),
+            "categories" => TomlValue::Array(
+                self.categories
+                    .iter()
+                    .cloned()
+                    .map(TomlValue::String)
+                    .collect(),
+            ),
+            "output" => TomlValue::String(self.output.name().into()),
+            "normalize_descriptions" => TomlValue::Boolean(self.normalize_descriptions),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+fn setting_kind(key: &str) -> Option<Kind> {
+    SETTINGS
+        .iter()
+        .find(|(known, _)| *known == key)
+        .map(|(_, kind)| *kind)
+}
+
+fn text(value: &TomlValue) -> Result<&str, String> {
+    value.as_str().ok_or_else(|| "expected text".into())
+}
+
+fn number(value: &TomlValue, min: usize, max: usize) -> Result<usize, String> {
+    value
+        .as_integer()
+        .and_then(|number| usize::try_from(number).ok())
+        .filter(|number| (min..=max).contains(number))
+        .ok_or_else(|| format!("expected a whole number from {min} to {max}"))
+}
+
+fn invalid_setting(path: &Path, key: &str, message: String) -> Error {
+    Error::InvalidSetting {
+        path: path.to_owned(),
+        key: key.into(),
+        message,
+    }
+}
+
+fn unknown_setting(config_folder: &Path, key: &str) -> Error {
+    invalid_setting(
+        &config_folder.join(CONFIG_FILE_NAME),
+        key,
+        no_such_setting(),
+    )
+}
+
+fn no_such_setting() -> String {
+    let keys: Vec<_> = SETTINGS.iter().map(|(key, _)| *key).collect();
+
+    format!(
+        "there's no such setting, the known ones are {}",
+        keys.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use fs_err as fs;
+
+    use super::{Config, OutputFormat, CONFIG_FILE_NAME};
+    use crate::Error;
+
+    #[test]
+    fn loads_and_changes_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        fs::write(&path, "# Shown before totals\ncurrency = \"US$\"\n").unwrap();
+
+        Config::set(dir.path(), "categories", "food, transport").unwrap();
+        Config::set(dir.path(), "width", "100").unwrap();
+        Config::set(dir.path(), "output", "json").unwrap();
+        Config::set(dir.path(), "normalize_descriptions", "true").unwrap();
+
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.currency, "US$");
+        assert_eq!(config.categories, ["food", "transport"]);
+        assert_eq!(config.width, 100);
+        assert_eq!(config.output, OutputFormat::Json);
+        assert!(config.normalize_descriptions);
+        assert_eq!(
+            config.get(dir.path(), "categories").unwrap(),
+            "food,transport"
+        );
+        assert!(config.list().contains("width = 100\n"));
+
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("# Shown before totals\n"));
+
+        assert!(config.check_category("food").is_ok());
+        assert!(matches!(
+            config.check_category("fun"),
+            Err(Error::UnknownCategory { .. })
+        ));
+    }
+
+    #[test]
+    fn names_the_invalid_setting() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let key_of = |err| match err {
+            Error::InvalidSetting { key, .. } => key,
+            other => panic!("expected an invalid setting, got {other:?}"),
+        };
+
+        let err = Config::set(dir.path(), "width", "wide").unwrap_err();
+        assert_eq!(key_of(err), "width");
+        let err = Config::set(dir.path(), "colour", "blue").unwrap_err();
+        assert_eq!(key_of(err), "colour");
+
+        fs::write(dir.path().join(CONFIG_FILE_NAME), "theme = \"sparkly\"\n").unwrap();
+        let err = Config::load(dir.path()).unwrap_err();
+        assert_eq!(key_of(err), "theme");
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    fmt,
+    ops::Not,
+    path::{Path, PathBuf},
+};
+
+use crate::parser::ParseError;
+
+const TOML_HINT: &str = "check for missing quotes, commas or brackets around this point";
+
+/// A problem found in a bookkeeping file, rendered like a compiler diagnostic:
+///
+/// ```text
+/// '3x' is not a valid month day
+///  --> ~/.local/share/porquinho/10-2024:3:4
+///   |
+/// 3 |   "3x - 12.00 Lunch",
+///   |    ^^
+///   = hint: the day must be a number between 1 and 31
+/// ```
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub path: PathBuf,
+    /// 1-based line of the problem
+    pub line: usize,
+    /// 1-based column, in characters, where the offending text starts
+    pub column: usize,
+    /// The full line of the file where the offending text is
+    pub source_line: String,
+    /// Length, in characters, of the offending text
+    pub length: usize,
+    pub hint: Option<String>,
+}
+
+/// The contents of a bookkeeping file, used to point out where problems are
+pub struct SourceFile<'a> {
+    pub path: &'a Path,
+    pub contents: &'a str,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn new(path: &'a Path, contents: &'a str) -> Self {
+        Self { path, contents }
+    }
+
+    /// Diagnoses a file that isn't valid TOML
+    pub fn diagnose_toml(&self, err: &toml::de::Error) -> Diagnostic {
+        let message = err.to_string();
+        // The location is shown separately, so remove it from the message
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_owned(),
+            None => message,
+        };
+
+        let (line, column) = err.line_col().unwrap_or_default();
+
+        self.diagnostic(message, line, column, 1, TOML_HINT)
+    }
+
+    /// Diagnoses a problem with the `occurrence`-th (0-based) appearance of the string `text`
+    pub fn diagnose_string(
+        &self,
+        text: &str,
+        occurrence: usize,
+        message: impl Into<String>,
+        hint: &str,
+    ) -> Diagnostic {
+        let (line, column) = self.locate_string(text, occurrence, 0);
+        let length = text.chars().count();
+
+        self.diagnostic(message.into(), line, column, length, hint)
+    }
+
+    /// Diagnoses an `operation` of this file that could not be parsed
+    pub fn diagnose_operation(&self, operation: &str, err: &ParseError) -> Diagnostic {
+        // Point at the specific bit of the operation that is wrong, if possible
+        let offending = err.offending_text().trim();
+        let (offset, length) = match operation.find(offending) {
+            Some(offset) if offending.is_empty().not() => (offset, offending.chars().count()),
+            _ => (0, operation.chars().count()),
+        };
+
+        let (line, column) = self.locate_string(operation, 0, offset);
+
+        self.diagnostic(err.to_string(), line, column, length, err.hint())
+    }
+
+    /// Diagnoses an operation record, the `index`-th (0-based) table of the `array_key`
+    /// array of tables, that could not be parsed
+    pub fn diagnose_record(&self, array_key: &str, index: usize, err: &ParseError) -> Diagnostic {
+        self.diagnose_in_record(
+            array_key,
+            index,
+            err.offending_text().trim(),
+            err.to_string(),
+            err.hint(),
+        )
+    }
+
+    /// Diagnoses a problem with the text `needle` inside of the `index`-th (0-based)
+    /// table of the `array_key` array of tables, or with its header if `needle` is empty
+    pub fn diagnose_in_record(
+        &self,
+        array_key: &str,
+        index: usize,
+        needle: &str,
+        message: impl Into<String>,
+        hint: &str,
+    ) -> Diagnostic {
+        let header = format!("[[{array_key}]]");
+
+        let (start, length) = match self.find_nth(&header, index) {
+            Some(header_start) => {
+                // The record spans until the next table header
+                let record = &self.contents[header_start..];
+                let record_end = record
+                    .match_indices("\n[")
+                    .next()
+                    .map_or(record.len(), |(end, _)| end);
+
+                match record[..record_end].find(needle) {
+                    Some(offset) if needle.is_empty().not() => {
+                        (header_start + offset, needle.chars().count())
+                    }
+                    _ => (header_start, header.len()),
+                }
+            }
+            None => (0, 1),
+        };
+
+        let (line, column) = self.position_of(start);
+
+        self.diagnostic(message.into(), line, column, length, hint)
+    }
+
+    /// Diagnoses a problem with the top-level `key` of the file, or with the file as
+    /// a whole if the key is missing
+    pub fn diagnose_key(&self, key: &str, message: impl Into<String>, hint: &str) -> Diagnostic {
+        let is_key_line = |line: &&str| {
+            line.strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        };
+
+        let position = self.contents.lines().position(|line| is_key_line(&line));
+        let (line, length) = match position {
+            Some(line) => (line, key.len()),
+            None => (0, 1),
+        };
+
+        self.diagnostic(message.into(), line, 0, length, hint)
+    }
+
+    /// Finds the 0-based line and column of the `offset`-th byte of the `occurrence`-th
+    /// appearance of the string `text` inside of the file
+    fn locate_string(&self, text: &str, occurrence: usize, offset: usize) -> (usize, usize) {
+        // Prefer to find the string with its quotes, so that "3 - 5.00 Bus"
+        // doesn't match inside of "13 - 5.00 Bus"
+        let quoted = ["\"", "'"]
+            .into_iter()
+            .find_map(|quote| self.find_nth(&format!("{quote}{text}{quote}"), occurrence))
+            .map(|start| start + 1);
+
+        let start = match quoted.or_else(|| self.find_nth(text, occurrence)) {
+            Some(start) => start + offset,
+            // The text might have been escaped in the file, point at where the file begins
+            None => return (0, 0),
+        };
+
+        self.position_of(start)
+    }
+
+    /// The 0-based line and column of the byte at `offset`
+    fn position_of(&self, offset: usize) -> (usize, usize) {
+        let before = &self.contents[..offset];
+        let line = before.matches('\n').count();
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        let column = before[line_start..].chars().count();
+
+        (line, column)
+    }
+
+    fn find_nth(&self, text: &str, occurrence: usize) -> Option<usize> {
+        self.contents
+            .match_indices(text)
+            .nth(occurrence)
+            .map(|(start, _)| start)
+    }
+
+    fn diagnostic(
+        &self,
+        message: String,
+        line: usize,
+        column: usize,
+        length: usize,
+        hint: &str,
+    ) -> Diagnostic {
+        let source_line = self.contents.lines().nth(line).unwrap_or_default();
+
+        Diagnostic {
+            message,
+            path: self.path.to_owned(),
+            line: line + 1,
+            column: column + 1,
+            source_line: source_line.to_owned(),
+            length: length.max(1),
+            hint: Some(hint.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        writeln!(f, "{}", self.message)?;
+        writeln!(
+            f,
+            "{gutter}--> {}:{}:{}",
+            self.path.display(),
+            self.line,
+            self.column
+        )?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line_number} | {}", self.source_line)?;
+        write!(
+            f,
+            "{gutter} | {}{}",
+            " ".repeat(self.column - 1),
+            "^".repeat(self.length)
+        )?;
+
+        if let Some(hint) = &self.hint {
+            write!(f, "\n{gutter} = hint: {hint}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Not, path::Path, str::FromStr};
+
+    use super::SourceFile;
+    use crate::parser::{Operation, OperationType};
+
+    #[test]
+    fn points_at_the_offending_part_of_an_operation() {
+        let contents = "take = [\n  \"3 - 12.00 Lunch\",\n  \"3x - 12.00 Dinner\",\n]\n";
+        let source = SourceFile::new(Path::new("10-2024"), contents);
+
+        let operation = "3x - 12.00 Dinner";
+        let err = Operation::from_str(operation).unwrap_err();
+        let diagnostic = source.diagnose_operation(operation, &err);
+
+        assert_eq!((diagnostic.line, diagnostic.column), (3, 4));
+        assert_eq!(
+            diagnostic.to_string(),
+            "'3x' is not a valid month day\n --> 10-2024:3:4\n  |\n3 |   \"3x - 12.00 Dinner\",\n  |    ^^\n  = hint: the day must be a number between 1 and 31"
+        );
+    }
+
+    #[test]
+    fn points_at_the_offending_field_of_a_record() {
+        let contents = "[[take]]\nday = 3\namount = \"12\"\ndescription = \"Lunch\"\n\n[[take]]\nday = 4\namount = \"1,5\"\ndescription = \"Bus\"\n";
+        let source = SourceFile::new(Path::new("10-2024"), contents);
+
+        let record = toml::from_str(&contents[contents.rfind("day").unwrap()..]).unwrap();
+        let err = Operation::from_record(OperationType::Withdraw, &record).unwrap_err();
+        let diagnostic = source.diagnose_record("take", 1, &err);
+
+        assert_eq!((diagnostic.line, diagnostic.column), (8, 11));
+        assert_eq!(diagnostic.length, 3);
+
+        let diagnostic = source.diagnose_in_record("take", 1, "", "problem", "hint");
+        assert_eq!((diagnostic.line, diagnostic.column), (6, 1));
+    }
+
+    #[test]
+    fn points_at_invalid_toml() {
+        let contents = "take = [\n  \"3 - 12.00 Lunch\"\n  \"4 - 12.00 Dinner\",\n]\n";
+        let source = SourceFile::new(Path::new("10-2024"), contents);
+
+        let err = contents.parse::<toml::Value>().unwrap_err();
+        let diagnostic = source.diagnose_toml(&err);
+
+        assert_eq!(diagnostic.line, 3);
+        assert!(diagnostic.message.contains(" at line ").not());
+    }
+}
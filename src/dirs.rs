@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeSet,
     ops::Not,
     path::{Path, PathBuf},
 };
@@ -8,16 +9,29 @@ use fs_err as fs;
 
 use crate::{Error, Result};
 
+/// Folder inside of the data and config folders where the named profiles live
+const PROFILES_FOLDER: &str = "profiles";
+
+/// Whether `name` can name a profile, which keeps it from escaping the profiles folder
+pub fn is_valid_profile_name(name: &str) -> bool {
+    name.is_empty().not()
+        && name
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '-' || char == '_')
+}
+
 pub struct Dirs {
-    inner: ProjectDirs,
+    config: PathBuf,
+    data: PathBuf,
 }
 
 impl Dirs {
-    pub fn init() -> Result<Self> {
-        let inner =
-            ProjectDirs::from("com", "vrmiguel", "porquinho").ok_or(Error::NoValidHomeDirFound)?;
-
-        let this = Self { inner };
+    /// The folders of `profile`, or of the default profile if not given, creating them if needed
+    ///
+    /// `data_dir` replaces the default data folder, and the books of named profiles
+    /// are kept inside of it as well.
+    pub fn init(data_dir: Option<PathBuf>, profile: Option<&str>) -> Result<Self> {
+        let this = Self::locate(data_dir, profile)?;
 
         this.create_dir_if_not_existent(this.config())?;
         this.create_dir_if_not_existent(this.data())?;
@@ -25,21 +39,99 @@ impl Dirs {
         Ok(this)
     }
 
+    /// Names of the profiles that have books or settings, sorted
+    pub fn profile_names(data_dir: Option<PathBuf>) -> Result<Vec<String>> {
+        let base = Self::locate(data_dir, None)?;
+        let mut names = BTreeSet::new();
+
+        for folder in [base.config(), base.data()] {
+            let profiles = folder.join(PROFILES_FOLDER);
+            if profiles.is_dir().not() {
+                continue;
+            }
+
+            for entry in fs::read_dir(profiles)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if entry.file_type()?.is_dir() && is_valid_profile_name(&name) {
+                    names.insert(name);
+                }
+            }
+        }
+
+        Ok(names.into_iter().collect())
+    }
+
+    fn locate(data_dir: Option<PathBuf>, profile: Option<&str>) -> Result<Self> {
+        let inner =
+            ProjectDirs::from("com", "vrmiguel", "porquinho").ok_or(Error::NoValidHomeDirFound)?;
+
+        let mut config = inner.config_dir().to_owned();
+        let mut data = data_dir.unwrap_or_else(|| inner.data_dir().to_owned());
+
+        if let Some(profile) = profile {
+            config = config.join(PROFILES_FOLDER).join(profile);
+            data = data.join(PROFILES_FOLDER).join(profile);
+        }
+
+        Ok(Self { config, data })
+    }
+
     fn create_dir_if_not_existent(&self, path: &Path) -> Result<()> {
         if path.exists().not() {
             fs::create_dir_all(path)
                 .map_err(|_| Error::CouldNotCreateFolder(PathBuf::from(path)))?;
-            println!("info: created folder {:?}", path);
+            log::info!("created folder {}", path.display());
         }
 
         Ok(())
     }
 
     pub fn config(&self) -> &Path {
-        self.inner.config_dir()
+        &self.config
     }
 
     pub fn data(&self) -> &Path {
-        self.inner.data_dir()
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use fs_err as fs;
+
+    use super::Dirs;
+
+    #[test]
+    fn keeps_profiles_apart() {
+        let data_dir = PathBuf::from("/tmp/ledger");
+
+        let default = Dirs::locate(Some(data_dir.clone()), None).unwrap();
+        assert_eq!(default.data(), data_dir);
+
+        let household = Dirs::locate(Some(data_dir.clone()), Some("household")).unwrap();
+        assert_eq!(household.data(), data_dir.join("profiles/household"));
+        assert!(household.config().ends_with("profiles/household"));
+
+        let personal = Dirs::locate(None, Some("personal")).unwrap();
+        assert!(personal.data().ends_with("profiles/personal"));
+        assert_ne!(personal.config(), household.config());
+    }
+
+    #[test]
+    fn lists_profile_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let profiles = dir.path().join("profiles");
+        fs::create_dir_all(profiles.join("household")).unwrap();
+        fs::create_dir_all(profiles.join("not a profile")).unwrap();
+        fs::write(profiles.join("notes.txt"), "").unwrap();
+
+        let names = Dirs::profile_names(Some(dir.path().to_owned())).unwrap();
+        assert!(names.contains(&"household".to_owned()));
+        assert!(names
+            .iter()
+            .all(|name| name != "not a profile" && name != "notes.txt"));
     }
 }
@@ -1,5 +1,9 @@
 use std::{io, path::PathBuf};
 
+use bigdecimal::BigDecimal;
+
+use crate::diagnostic::Diagnostic;
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Debug, thiserror::Error)]
@@ -10,20 +14,215 @@ pub enum Error {
     CouldNotCreateFolder(PathBuf),
     #[error("Filesystem error: {0}")]
     FileSystem(#[from] io::Error),
+    #[error("Toml error: {0}")]
+    Toml(#[from] toml_edit::TomlError),
     #[error("Parsing error: {0}")]
     Parse(#[from] crate::parser::ParseError),
+    #[error("{0}")]
+    Diagnostic(Box<Diagnostic>),
     #[error("Invalid UTF-8: {0}")]
     Utf8(#[from] std::str::Utf8Error),
-    #[error("Toml values are in ??? unexpected types: {description}. at {path}.")]
+    #[error("{0} is being modified by another porquinho process, try again later")]
+    Locked(PathBuf),
+    #[error(
+        "{path} is in version {version} of the bookkeeping format, but this build of porquinho \
+         only understands up to version {}. Please upgrade porquinho",
+        crate::migration::CURRENT_FORMAT_VERSION
+    )]
+    UnsupportedFormatVersion { path: PathBuf, version: i64 },
+    #[error("Found {0} problem(s) in the bookkeeping files")]
+    CheckFailed(usize),
+    #[error(
+        "Unexpected value types in {path}: {description}. Run `porquinho check --fix` to repair it"
+    )]
     InvalidTomlTypes { description: String, path: PathBuf },
+    #[cfg(feature = "sqlite")]
+    #[error("Database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("This build of porquinho can't use SQLite, rebuild it with `--features sqlite`")]
+    SqliteUnsupported,
+    #[error("The books are already stored in {0}")]
+    AlreadyStoredIn(PathBuf),
+    #[error("Couldn't find git, is it installed?")]
+    GitNotFound,
+    #[error("`git {command}` failed: {message}")]
+    Git { command: String, message: String },
+    #[error("{0} has no history yet, run porquinho with `--git` to start recording it")]
+    NoHistory(PathBuf),
+    #[error(
+        "This build of porquinho can't encrypt the books, rebuild it with `--features encryption`"
+    )]
+    EncryptionUnsupported,
+    #[error("Wrong passphrase")]
+    WrongPassphrase,
+    #[error("The passphrases don't match")]
+    PassphraseMismatch,
+    #[error("{0} couldn't be decrypted, it might be corrupted")]
+    Undecryptable(PathBuf),
+    #[error(
+        "{0} is encrypted, but there's no {} next to it to decrypt it with",
+        crate::vault::KEY_FILE_NAME
+    )]
+    MissingKey(PathBuf),
+    #[error("The books in {0} aren't encrypted")]
+    NotEncrypted(PathBuf),
+    #[error("Only books stored in TOML files can be encrypted")]
+    EncryptedSqlite,
+    #[error("Invalid setting `{key}` in {path}: {message}")]
+    InvalidSetting {
+        path: PathBuf,
+        key: String,
+        message: String,
+    },
+    #[error(
+        "Unknown category `{category}`, the ones in your settings are: {known}. \
+         Add it with `porquinho config set categories`"
+    )]
+    UnknownCategory { category: String, known: String },
+    #[error(
+        "The amount and description are missing, and they can only be asked for in a terminal"
+    )]
+    MissingOperation,
+    #[error("The input ended before the operation was confirmed, so nothing was added")]
+    InputEnded,
+    #[error("This build of porquinho has no terminal interface, rebuild it with `--features tui`")]
+    TuiUnsupported,
+    #[error("Invalid regular expression: {0}")]
+    InvalidRegex(#[from] regex::Error),
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    #[error(
+        "The operations of {0} were changed by another porquinho process meanwhile, try again"
+    )]
+    OperationChanged(String),
+    #[error("There's no `{0}` command")]
+    UnknownCommand(String),
+    #[error("{spent} was spent in {month}, over its target of {target}")]
+    BudgetExceeded {
+        month: String,
+        spent: BigDecimal,
+        target: i64,
+    },
+}
+
+impl From<Diagnostic> for Error {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self::Diagnostic(Box::new(diagnostic))
+    }
+}
+
+/// Kinds of failures that porquinho exits with different codes for, so that
+/// scripts can react to each of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Anything that doesn't fit the other classes, such as git failing
+    Other,
+    /// The command can't be run as asked, e.g. with the wrong passphrase
+    Usage,
+    /// More was spent in a month than its target, when asked to check it
+    BudgetExceeded,
+    /// The books or settings have invalid contents
+    Data,
+    /// Files or folders couldn't be read or written
+    Io,
+    /// Another porquinho process is changing the books, trying again later may work
+    Locked,
+}
+
+impl ErrorClass {
+    /// Every class, ordered by exit code
+    pub const ALL: [Self; 6] = [
+        Self::Other,
+        Self::Usage,
+        Self::BudgetExceeded,
+        Self::Data,
+        Self::Io,
+        Self::Locked,
+    ];
+
+    /// The code the process exits with, following `sysexits.h` where it has a match
+    ///
+    /// Errors in the command line arguments are reported by clap, also with code 2.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Other => 1,
+            Self::Usage => 2,
+            Self::BudgetExceeded => 3,
+            Self::Data => 65,
+            Self::Io => 74,
+            Self::Locked => 75,
+        }
+    }
+
+    /// What exiting with the code of this class means, for the documentation
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Other => "Any other failure, e.g. git couldn't record the history",
+            Self::Usage => {
+                "Invalid usage, e.g. bad arguments, an unknown category or wrong passphrase"
+            }
+            Self::BudgetExceeded => {
+                "More was spent than the target of the month, with `status --check-target`"
+            }
+            Self::Data => "The books or settings have invalid contents, including a failed `check`",
+            Self::Io => "Files or folders couldn't be read or written",
+            Self::Locked => "Another porquinho process is changing the books, try again later",
+        }
+    }
+}
+
+impl Error {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Self::Locked(_) | Self::OperationChanged(_) => ErrorClass::Locked,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(err)
+                if err.sqlite_error_code() == Some(rusqlite::ErrorCode::DatabaseBusy) =>
+            {
+                ErrorClass::Locked
+            }
+            Self::NoValidHomeDirFound | Self::CouldNotCreateFolder(_) | Self::FileSystem(_) => {
+                ErrorClass::Io
+            }
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(_) => ErrorClass::Io,
+            Self::Toml(_)
+            | Self::Parse(_)
+            | Self::Diagnostic(_)
+            | Self::Utf8(_)
+            | Self::UnsupportedFormatVersion { .. }
+            | Self::CheckFailed(_)
+            | Self::InvalidTomlTypes { .. }
+            | Self::Undecryptable(_)
+            | Self::MissingKey(_)
+            | Self::InvalidSetting { .. } => ErrorClass::Data,
+            Self::SqliteUnsupported
+            | Self::AlreadyStoredIn(_)
+            | Self::NoHistory(_)
+            | Self::EncryptionUnsupported
+            | Self::WrongPassphrase
+            | Self::PassphraseMismatch
+            | Self::NotEncrypted(_)
+            | Self::EncryptedSqlite
+            | Self::UnknownCategory { .. }
+            | Self::MissingOperation
+            | Self::InputEnded
+            | Self::UnknownCommand(_)
+            | Self::TuiUnsupported
+            | Self::InvalidRegex(_)
+            | Self::InvalidQuery(_) => ErrorClass::Usage,
+            Self::BudgetExceeded { .. } => ErrorClass::BudgetExceeded,
+            Self::GitNotFound | Self::Git { .. } => ErrorClass::Other,
+        }
+    }
 }
 
 pub struct TomlTypeCheck {
-    pub is_take_array: bool,
-    pub is_put_array: bool,
+    pub is_take_array_or_undefined: bool,
+    pub is_put_array_or_undefined: bool,
     pub is_target_int_or_undefined: bool,
-    pub is_take_array_of_strings: bool,
-    pub is_put_array_of_strings: bool,
+    pub is_take_array_of_tables: bool,
+    pub is_put_array_of_tables: bool,
 }
 
 pub struct TomlTypeCheckDiagnosis(String);
@@ -39,33 +238,116 @@ impl TomlTypeCheckDiagnosis {
 }
 
 impl TomlTypeCheck {
+    /// The keys whose values are of unexpected types, along with what's wrong with each
+    pub fn failures(&self) -> Vec<(&'static str, &'static str)> {
+        let checks = [
+            (
+                !self.is_take_array_or_undefined,
+                "take",
+                "take is not an array",
+            ),
+            (
+                !self.is_put_array_or_undefined,
+                "put",
+                "put is not an array",
+            ),
+            (
+                !self.is_target_int_or_undefined,
+                "target",
+                "target is not a integer",
+            ),
+            (
+                self.is_take_array_or_undefined && !self.is_take_array_of_tables,
+                "take",
+                "take array contains an element that is not an operation record",
+            ),
+            (
+                self.is_put_array_or_undefined && !self.is_put_array_of_tables,
+                "put",
+                "put array contains an element that is not an operation record",
+            ),
+        ];
+
+        checks
+            .into_iter()
+            .filter(|(failed, _, _)| *failed)
+            .map(|(_, key, description)| (key, description))
+            .collect()
+    }
+
     pub fn into_diagnosis(self) -> TomlTypeCheckDiagnosis {
         // comma separated description of check failure reasons
-        let mut description = String::new();
-
-        // if boolean is true, append text to the description, separating with commas
-        let mut describe = |boolean, text| {
-            if boolean {
-                if !description.is_empty() {
-                    description.push_str(", ");
-                }
-                description.push_str(text);
-            }
-        };
+        let descriptions: Vec<_> = self
+            .failures()
+            .into_iter()
+            .map(|(_, description)| description)
+            .collect();
 
-        describe(!self.is_take_array, "");
-        describe(!self.is_put_array, "");
-        describe(!self.is_target_int_or_undefined, "target is not a integer");
+        TomlTypeCheckDiagnosis(descriptions.join(", "))
+    }
+}
 
-        describe(
-            self.is_take_array && !self.is_take_array_of_strings,
-            "take array contains a non-string element",
-        );
-        describe(
-            self.is_put_array && !self.is_put_array_of_strings,
-            "put array contains a non-string element",
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, io, path::PathBuf};
+
+    use super::{Error, ErrorClass};
+    use crate::parser::ParseError;
+
+    #[test]
+    fn classifies_errors() {
+        let cases = [
+            (Error::Locked(PathBuf::from("10-2026")), ErrorClass::Locked),
+            (
+                Error::FileSystem(io::ErrorKind::PermissionDenied.into()),
+                ErrorClass::Io,
+            ),
+            (
+                Error::Parse(ParseError::InvalidDay("32".into())),
+                ErrorClass::Data,
+            ),
+            (Error::CheckFailed(2), ErrorClass::Data),
+            (Error::WrongPassphrase, ErrorClass::Usage),
+            (Error::GitNotFound, ErrorClass::Other),
+            (
+                Error::BudgetExceeded {
+                    month: "10-2026".into(),
+                    spent: 3100.into(),
+                    target: 3000,
+                },
+                ErrorClass::BudgetExceeded,
+            ),
+        ];
+
+        for (error, class) in cases {
+            assert_eq!(error.class(), class, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn describes_values_of_unexpected_types() {
+        let error = Error::InvalidTomlTypes {
+            description: "target is not a integer".into(),
+            path: PathBuf::from("10-2026"),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "Unexpected value types in 10-2026: target is not a integer. \
+             Run `porquinho check --fix` to repair it"
         );
+    }
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let codes: HashSet<_> = ErrorClass::ALL
+            .iter()
+            .map(|class| class.exit_code())
+            .collect();
 
-        TomlTypeCheckDiagnosis(description)
+        assert_eq!(codes.len(), ErrorClass::ALL.len());
+        assert_eq!(ErrorClass::BudgetExceeded.exit_code(), 3);
+        // 0 is success, and 126 to 128 are what shells use when a command can't run
+        assert!(codes.iter().all(|code| (1..126).contains(code)));
     }
 }
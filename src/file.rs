@@ -1,9 +1,17 @@
-use std::{io::Write, path::Path, str};
+use std::{
+    io::Write,
+    ops::Not,
+    path::{Path, PathBuf},
+    str,
+};
 
-use chrono::{Datelike, Local};
+use chrono::{Datelike, Local, NaiveDate};
 use fs_err as fs;
 
+use crate::Result;
+
 /// Represents the filename of a Porquinho bookkeeping file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BookkeepingFile {
     name: [u8; 7],
 }
@@ -25,11 +33,52 @@ impl BookkeepingFile {
         Self { name: buf }
     }
 
-    pub fn as_path(&self) -> &Path {
-        // Safety: `current_file` must never make `self.name` be invalid UTF-8
-        let filename = unsafe { str::from_utf8_unchecked(&self.name) };
+    /// The bookkeeping file named `file_name`, if it is in the `MM-YYYY` format
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let (month, year) = file_name.split_once('-')?;
+
+        let is_number =
+            |text: &str, len| text.len() == len && text.bytes().all(|byte| byte.is_ascii_digit());
+        if is_number(month, 2).not() || is_number(year, 4).not() {
+            return None;
+        }
+        if (1..=12).contains(&month.parse::<u32>().ok()?).not() {
+            return None;
+        }
+
+        let mut name = [0; 7];
+        name.copy_from_slice(file_name.as_bytes());
+
+        Some(Self { name })
+    }
+
+    /// The bookkeeping file of the given month, if it is in the `MM-YYYY` range
+    pub fn from_year_and_month(year: i32, month: u32) -> Option<Self> {
+        Self::from_file_name(&format!("{month:02}-{year:04}"))
+    }
+
+    pub fn month(&self) -> u32 {
+        // Safety: `name` always starts with two ASCII digits
+        self.as_str()[..2].parse().unwrap()
+    }
 
-        Path::new(filename)
+    pub fn year(&self) -> i32 {
+        // Safety: `name` always ends with four ASCII digits
+        self.as_str()[3..].parse().unwrap()
+    }
+
+    /// Whether `day` exists in the month of this file
+    pub fn has_day(&self, day: u8) -> bool {
+        NaiveDate::from_ymd_opt(self.year(), self.month(), day.into()).is_some()
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Safety: `current_file` and `from_file_name` must never make `self.name` be invalid UTF-8
+        unsafe { str::from_utf8_unchecked(&self.name) }
+    }
+
+    pub fn as_path(&self) -> &Path {
+        Path::new(self.as_str())
     }
 }
 
@@ -41,6 +90,72 @@ pub fn create_file_if_not_existent(path: &Path) {
         .open(path)
         .is_ok()
     {
-        println!("Created {}", path.display());
+        log::info!("created {}", path.display());
+    }
+}
+
+/// Replaces the contents of the file at `path` with `contents`.
+///
+/// The new contents are first written and flushed to a temporary file in the
+/// same folder, which is then renamed over `path`. A crash or a full disk midway
+/// through leaves either the old or the new version in place, never a mix of both.
+///
+/// The previous version of the file, if any, is kept alongside it as `<name>.bak`.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    write_replacing(path, contents, true)
+}
+
+/// Replaces the contents of the file at `path` like [`write_atomically`], but
+/// without keeping its previous version
+pub fn replace_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    write_replacing(path, contents, false)
+}
+
+fn write_replacing(path: &Path, contents: &[u8], keep_backup: bool) -> Result<()> {
+    let temp_path = sibling_path(path, "tmp");
+
+    if let Err(err) = write_and_sync(&temp_path, contents) {
+        // The temporary file is useless if incomplete, so we don't care if removing it fails
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+
+    if keep_backup && path.exists() {
+        fs::copy(path, sibling_path(path, "bak"))?;
     }
+
+    fs::rename(&temp_path, path)?;
+    sync_parent_folder(path)
+}
+
+/// The path of a file that lives next to `path`, named `<name>.<extension>`
+pub fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_owned();
+    file_name.push(".");
+    file_name.push(extension);
+
+    path.with_file_name(file_name)
+}
+
+fn write_and_sync(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all().map_err(Into::into)
+}
+
+/// Makes sure the rename of a file inside of the parent folder reached the disk
+#[cfg(unix)]
+fn sync_parent_folder(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(parent) if parent.as_os_str().is_empty().not() => {
+            fs::File::open(parent)?.sync_all().map_err(Into::into)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Folders can't be opened as files on Windows, where renames are already durable
+#[cfg(not(unix))]
+fn sync_parent_folder(_path: &Path) -> Result<()> {
+    Ok(())
 }
@@ -0,0 +1,253 @@
+use std::{
+    io::ErrorKind,
+    ops::Not,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use fs_err as fs;
+
+use crate::{file::BookkeepingFile, Error, Result};
+
+/// Files kept next to the books that aren't worth recording, along with the
+/// books of the named profiles, which have histories of their own
+const IGNORED_PATTERNS: [&str; 5] = ["*.bak", "*.lock", "*.tmp", "*.migrated", "/profiles/"];
+
+/// What is committed and logged, which is the books' folder without the named profiles
+/// kept inside of the default one, even if the repository was tracking them already
+const BOOKS_PATHSPEC: [&str; 2] = [".", ":(exclude)profiles"];
+
+/// The history of the books, recorded as commits of a git repository in their folder
+///
+/// Git is run as an external program, so it uses the user's own configuration,
+/// such as their name and email.
+pub struct GitHistory {
+    folder: PathBuf,
+}
+
+impl GitHistory {
+    /// The history of the books in `folder`, if it is inside of a git repository
+    ///
+    /// Repositories that ignore `folder` don't count, such as the one of the default
+    /// profile for the named profiles inside of its folder.
+    pub fn find(folder: &Path) -> Result<Option<Self>> {
+        let history = Self { folder: folder.to_owned() };
+
+        let inside = history.run(&["rev-parse", "--is-inside-work-tree"])?;
+        if inside.status.success().not() {
+            return Ok(None);
+        }
+
+        let ignored = history.run(&["check-ignore", "--quiet", "--no-index", "."])?;
+        Ok(ignored.status.success().not().then_some(history))
+    }
+
+    /// The history of the books in `folder`, starting a repository there if needed
+    pub fn find_or_init(folder: &Path) -> Result<Self> {
+        let history = match Self::find(folder)? {
+            Some(history) => history,
+            None => {
+                let history = Self { folder: folder.to_owned() };
+                history.run_checked(&["init", "--quiet"])?;
+                log::info!("started recording the history of {}", folder.display());
+                history
+            }
+        };
+
+        history.ignore_leftover_files()?;
+
+        Ok(history)
+    }
+
+    /// Commits every change to the books with `message`, if anything changed
+    pub fn commit(&self, message: &str) -> Result<()> {
+        // Only the books' folder is touched, even if the repository is above it. Git refuses
+        // to exclude the ignored profiles when adding, so it's left to the commit
+        self.run_checked(&["add", "--all", "--", "."])?;
+
+        let staged =
+            self.run(&[&["diff", "--cached", "--quiet", "--"], &BOOKS_PATHSPEC[..]].concat())?;
+        if staged.status.success() {
+            return Ok(());
+        }
+
+        let commit = ["commit", "--quiet", "--message", message, "--"];
+        self.run_checked(&[&commit[..], &BOOKS_PATHSPEC[..]].concat())?;
+
+        Ok(())
+    }
+
+    /// The recorded changes to the books, newest first, optionally only the latest
+    /// `limit` ones and the ones that touched `month`
+    pub fn log(&self, month: Option<&BookkeepingFile>, limit: Option<usize>) -> Result<String> {
+        // Nothing has been recorded yet
+        if self
+            .run(&["rev-parse", "--verify", "--quiet", "HEAD"])?
+            .status
+            .success()
+            .not()
+        {
+            return Ok(String::new());
+        }
+
+        let limit = limit.map(|limit| format!("--max-count={limit}"));
+
+        let mut args = vec!["log", "--date=short", "--format=%h %ad %s"];
+        args.extend(limit.as_deref());
+        args.push("--");
+        match month {
+            Some(month) => args.push(month.as_str()),
+            None => args.extend(BOOKS_PATHSPEC),
+        }
+
+        let output = self.run_checked(&args)?;
+
+        String::from_utf8(output.stdout).map_err(|err| err.utf8_error().into())
+    }
+
+    /// Makes sure the backups, locks and temporary files kept next to the books,
+    /// as well as the books of other profiles, are left out of the history
+    fn ignore_leftover_files(&self) -> Result<()> {
+        let path = self.folder.join(".gitignore");
+        let mut contents = if path.exists() {
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+
+        let missing: Vec<_> = IGNORED_PATTERNS
+            .into_iter()
+            .filter(|pattern| contents.lines().any(|line| line.trim() == *pattern).not())
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        if contents.is_empty().not() && contents.ends_with('\n').not() {
+            contents.push('\n');
+        }
+        for pattern in missing {
+            contents.push_str(pattern);
+            contents.push('\n');
+        }
+
+        fs::write(&path, contents).map_err(Into::into)
+    }
+
+    fn run(&self, args: &[&str]) -> Result<Output> {
+        log::debug!(
+            "running `git {}` in {}",
+            args.join(" "),
+            self.folder.display()
+        );
+
+        let mut command = Command::new("git");
+        // Lets tests commit where git has no identity configured, without changing
+        // the environment shared by the tests running alongside them
+        #[cfg(test)]
+        command.args([
+            "-c",
+            "user.name=Porquinho",
+            "-c",
+            "user.email=porquinho@example.com",
+        ]);
+
+        let output = command.arg("-C").arg(&self.folder).args(args).output();
+
+        match output {
+            Ok(output) => Ok(output),
+            Err(err) if err.kind() == ErrorKind::NotFound => Err(Error::GitNotFound),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Runs git, failing if it does
+    fn run_checked(&self, args: &[&str]) -> Result<Output> {
+        let output = self.run(args)?;
+
+        if output.status.success().not() {
+            return Err(Error::Git {
+                command: args.join(" "),
+                message: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            });
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fs_err as fs;
+
+    use super::GitHistory;
+    use crate::file::BookkeepingFile;
+
+    #[test]
+    fn commits_changes_to_the_books_only() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(GitHistory::find(dir.path()).unwrap().is_none());
+
+        let history = GitHistory::find_or_init(dir.path()).unwrap();
+        fs::write(dir.path().join("10-2026"), "format_version = 2\n").unwrap();
+        fs::write(dir.path().join("10-2026.bak"), "").unwrap();
+        history.commit("take 42.10 Lunch (10-2026)").unwrap();
+
+        fs::write(dir.path().join("11-2026"), "format_version = 2\n").unwrap();
+        history.commit("put 100 Refund (11-2026)").unwrap();
+        // Nothing changed, so nothing is committed
+        history.commit("put 100 Refund (11-2026)").unwrap();
+
+        let log = history.log(None, None).unwrap();
+        let messages: Vec<_> = log
+            .lines()
+            .map(|line| line.splitn(3, ' ').nth(2).unwrap())
+            .collect();
+        assert_eq!(
+            messages,
+            ["put 100 Refund (11-2026)", "take 42.10 Lunch (10-2026)"]
+        );
+
+        let october = BookkeepingFile::from_file_name("10-2026").unwrap();
+        assert_eq!(
+            history.log(Some(&october), None).unwrap().lines().count(),
+            1
+        );
+        assert_eq!(history.log(None, Some(1)).unwrap().lines().count(), 1);
+
+        let gitignore = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert!(gitignore.contains("*.bak\n"));
+    }
+
+    #[test]
+    fn keeps_the_histories_of_profiles_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = dir.path().join("profiles/work");
+        fs::create_dir_all(&profile).unwrap();
+
+        let default = GitHistory::find_or_init(dir.path()).unwrap();
+        fs::write(dir.path().join("10-2026"), "format_version = 2\n").unwrap();
+        fs::write(profile.join("10-2026"), "format_version = 2\n").unwrap();
+        // Even books of a profile that the repository was already tracking are left out
+        default
+            .run_checked(&["add", "--force", "profiles/work/10-2026"])
+            .unwrap();
+        default.commit("take 42.10 Lunch (10-2026)").unwrap();
+
+        let committed = default
+            .run_checked(&["show", "--name-only", "--format=", "HEAD"])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(committed.stdout).unwrap(),
+            ".gitignore\n10-2026\n"
+        );
+
+        // The profile gets a history of its own rather than the ignoring one above it
+        assert!(GitHistory::find(&profile).unwrap().is_none());
+        let work = GitHistory::find_or_init(&profile).unwrap();
+        work.commit("put 100 Refund (10-2026)").unwrap();
+
+        assert_eq!(default.log(None, None).unwrap().lines().count(), 1);
+        assert_eq!(work.log(None, None).unwrap().lines().count(), 1);
+    }
+}
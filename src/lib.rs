@@ -0,0 +1,61 @@
+//! The bookkeeping behind the `porquinho` command line tool, for programs that
+//! want to read or change the same books
+//!
+//! Books are kept as a month per file in a folder (see [`storage::open`]), and a
+//! [`Bookkeeper`] keeps the operations of one of those months:
+//!
+//! ```
+//! use porquinho::{
+//!     file::BookkeepingFile,
+//!     parser::{Operation, OperationType},
+//!     storage::MemoryStorage,
+//!     Access, BigDecimal, Bookkeeper,
+//! };
+//!
+//! let october = BookkeepingFile::from_file_name("10-2026").unwrap();
+//! let storage = Box::new(MemoryStorage::new());
+//! let mut bookkeeper = Bookkeeper::load(storage, october, Access::ReadWrite)?;
+//!
+//! let lunch = Operation::new(3, OperationType::Withdraw, BigDecimal::from(12), "Lunch");
+//! bookkeeper.add_operation(lunch)?;
+//!
+//! assert_eq!(bookkeeper.status().take_total, BigDecimal::from(12));
+//! # Ok::<(), porquinho::Error>(())
+//! ```
+
+pub mod bookkeeper;
+mod check;
+mod completion;
+pub mod config;
+mod diagnostic;
+mod dirs;
+pub mod error;
+pub mod file;
+mod git;
+mod lock;
+mod migration;
+pub mod parser;
+mod payee;
+pub mod query;
+pub mod report;
+pub mod search;
+pub mod storage;
+pub mod vault;
+
+pub use bigdecimal::BigDecimal;
+
+pub use crate::{
+    bookkeeper::Bookkeeper,
+    error::{Error, Result},
+    storage::Access,
+};
+
+// What the front ends need out of the modules that are otherwise internal
+pub use crate::{
+    check::{check_folder, CheckReport},
+    completion::Candidates,
+    diagnostic::Diagnostic,
+    dirs::{is_valid_profile_name, Dirs},
+    git::GitHistory,
+    payee::{Payee, PayeeIndex},
+};
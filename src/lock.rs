@@ -0,0 +1,67 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use fs2::FileExt;
+use fs_err as fs;
+
+use crate::{file::sibling_path, Error, Result};
+
+/// How long to wait for another porquinho process to release a bookkeeping file
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(3);
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An advisory, exclusive lock over a bookkeeping file, released when dropped.
+///
+/// The lock is held over a `<name>.lock` file that lives next to the bookkeeping file
+/// instead of the bookkeeping file itself, since writes replace the latter with a brand new
+/// file (see [`write_atomically`](crate::file::write_atomically)).
+#[derive(Debug)]
+pub struct FileLock {
+    file: fs::File,
+}
+
+impl FileLock {
+    /// Locks the bookkeeping file at `path`, waiting up to `timeout` for
+    /// other processes to release it
+    pub fn acquire(path: &Path, timeout: Duration) -> Result<Self> {
+        let lock_path = sibling_path(path, "lock");
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(&lock_path)?;
+
+        let started_at = Instant::now();
+
+        loop {
+            match file.file().try_lock_exclusive() {
+                Ok(()) => {
+                    log::debug!("locked {}", path.display());
+                    return Ok(Self { file });
+                }
+                Err(err) if is_contended(&err) && started_at.elapsed() < timeout => {
+                    thread::sleep(RETRY_INTERVAL);
+                }
+                Err(err) if is_contended(&err) => {
+                    return Err(Error::Locked(PathBuf::from(path)));
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // The OS releases the lock once the file is closed anyway
+        let _ = self.file.file().unlock();
+    }
+}
+
+fn is_contended(err: &io::Error) -> bool {
+    err.kind() == fs2::lock_contended_error().kind()
+}
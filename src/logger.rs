@@ -0,0 +1,71 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Prints what porquinho is doing to stderr, so that stdout only has the
+/// results of commands and can be piped
+struct StderrLogger;
+
+static LOGGER: StderrLogger = StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Messages of dependencies are left out
+        metadata.level() <= log::max_level() && metadata.target().starts_with("porquinho")
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let level = match record.level() {
+                Level::Error => "error",
+                Level::Warn => "warning",
+                Level::Info => "info",
+                Level::Debug => "debug",
+                Level::Trace => "trace",
+            };
+
+            eprintln!("{level}: {}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Starts logging, showing only errors if `quiet`, or more details the
+/// higher `verbosity` is
+pub fn init(quiet: bool, verbosity: u8) {
+    // Only fails if a logger was already set, which makes this a no-op
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(level(quiet, verbosity));
+}
+
+fn level(quiet: bool, verbosity: u8) -> LevelFilter {
+    match (quiet, verbosity) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::LevelFilter;
+
+    use super::level;
+
+    #[test]
+    fn picks_the_level_of_the_flags() {
+        let cases = [
+            (false, 0, LevelFilter::Info),
+            (false, 1, LevelFilter::Debug),
+            (false, 2, LevelFilter::Trace),
+            (false, 5, LevelFilter::Trace),
+            (true, 0, LevelFilter::Error),
+            // clap refuses both flags together, but quiet would win
+            (true, 2, LevelFilter::Error),
+        ];
+
+        for (quiet, verbosity, expected) in cases {
+            assert_eq!(level(quiet, verbosity), expected, "{quiet} {verbosity}");
+        }
+    }
+}
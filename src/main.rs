@@ -1,30 +1,42 @@
-mod bookkeeper;
 mod cli;
-mod dirs;
-mod error;
-mod file;
-mod parser;
+mod logger;
+mod man;
+mod shell_scripts;
+#[cfg(feature = "tui")]
+mod tui;
+mod wizard;
 
-use chrono::{Datelike, Local};
-use clap::Parser;
-use dirs::Dirs;
-use parser::{Operation, OperationType};
+use std::{
+    io::{self, IsTerminal},
+    ops::Not,
+    path::PathBuf,
+};
 
-pub use crate::{
-    bookkeeper::Bookkeeper,
-    error::{Error, Result},
+use chrono::{Datelike, Local};
+use clap::{CommandFactory, Parser};
+use porquinho::{
+    bookkeeper::Sorting,
+    check_folder,
+    config::{Config, OutputFormat},
+    file::BookkeepingFile,
+    parser::{Operation, OperationType},
+    query::Query,
+    report::YearReport,
+    search::{Filter, SearchResults},
+    storage::{self, Backend},
+    vault::{self, Vault},
+    Access, BigDecimal, Bookkeeper, Candidates, Dirs, Error, GitHistory, PayeeIndex, Result,
 };
 
 use crate::{
-    cli::{Opts, Subcommand},
-    file::create_file_if_not_existent,
-    file::BookkeepingFile,
+    cli::{CompletionKind, ConfigAction, Opts, ReportPeriod, Subcommand},
+    wizard::Wizard,
 };
 
 fn main() {
     if let Err(err) = exec() {
         eprintln!("Error: {}", err);
-        std::process::exit(127);
+        std::process::exit(err.class().exit_code());
     }
 }
 
@@ -34,39 +46,381 @@ fn exec() -> Result<()> {
 
 struct GlobalState {
     cmd: Subcommand,
-    bookkeeper: Bookkeeper,
+    dirs: Dirs,
+    config: Config,
+    /// The data folder given on the command line, if any, which holds the named profiles
+    data_dir: Option<PathBuf>,
+    /// Whether changes are recorded in the git history of the books
+    git: bool,
 }
 
 impl GlobalState {
     pub fn new() -> Result<Self> {
-        let cmd = Opts::parse().cmd;
-        let dirs = Dirs::init()?;
+        let Opts {
+            git,
+            quiet,
+            verbose,
+            data_dir,
+            profile,
+            cmd,
+        } = Opts::parse();
+        logger::init(quiet, verbose);
 
-        let bk_path = dirs.data().join(BookkeepingFile::current_file().as_path());
-        create_file_if_not_existent(&bk_path);
-        let bookkeeper = Bookkeeper::load_from_path(bk_path)?;
+        let mut dirs = Dirs::init(data_dir.clone(), profile.as_deref())?;
+        let mut config = Self::load_config(&cmd, &dirs)?;
 
-        Ok(Self { cmd, bookkeeper })
+        // `config` always works on the profile asked for, so that the
+        // default profile can be changed back
+        let is_configuring = matches!(cmd, Subcommand::Config { .. });
+        if let (None, Some(default_profile), false) =
+            (&profile, config.default_profile.clone(), is_configuring)
+        {
+            dirs = Dirs::init(data_dir.clone(), Some(&default_profile))?;
+            config = Self::load_config(&cmd, &dirs)?;
+        }
+
+        Ok(Self {
+            cmd,
+            dirs,
+            config,
+            data_dir,
+            git,
+        })
+    }
+
+    fn load_config(cmd: &Subcommand, dirs: &Dirs) -> Result<Config> {
+        match cmd {
+            // Only the new value is checked, so that broken settings can be repaired
+            Subcommand::Config {
+                action: ConfigAction::Set { .. },
+            } => Ok(Config::default()),
+            // Broken settings don't keep these from being printed
+            Subcommand::Completions { .. } | Subcommand::Man { .. } => Ok(Config::default()),
+            _ => Config::load(dirs.config()),
+        }
+    }
+
+    /// Loads the bookkeeper for the current month
+    fn load_bookkeeper(&self) -> Result<Bookkeeper> {
+        let storage = storage::open(self.dirs.data(), Vault::unlock(self.dirs.data())?)?;
+
+        let access = if self.cmd.is_mutating() {
+            Access::ReadWrite
+        } else {
+            Access::ReadOnly
+        };
+
+        Bookkeeper::load(storage, BookkeepingFile::current_file(), access)
     }
 
     pub fn run_command(self) -> Result<()> {
-        let day = Local::today().day() as u8;
-        let Self { cmd, mut bookkeeper } = self;
+        let mut changes = vec![];
+        let result = self.execute_command(&mut changes);
 
-        match cmd {
-            Subcommand::Take { amount, ref description } => {
-                let operation = Operation::new(day, OperationType::Withdraw, amount, description);
-                bookkeeper.add_operation(operation)?;
+        // Whatever was changed is recorded, even if the command failed midway through
+        let recorded = self.record_change(&changes);
+
+        result.and(recorded)
+    }
+
+    /// Commits the changes made by the command to the history of the books, if enabled
+    fn record_change(&self, changes: &[String]) -> Result<()> {
+        if self.git.not() {
+            return Ok(());
+        }
+
+        match self.cmd.change_summary(changes) {
+            Some(message) => GitHistory::find_or_init(self.dirs.data())?.commit(&message),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs the command, adding what it stored in the books to `changes`
+    fn execute_command(&self, changes: &mut Vec<String>) -> Result<()> {
+        match self.cmd {
+            Subcommand::Take {
+                ref amount,
+                ref description,
+                ref category,
+            } => changes.extend(self.add_operation(
+                OperationType::Withdraw,
+                amount,
+                description,
+                category,
+            )?),
+            Subcommand::Put {
+                ref amount,
+                ref description,
+                ref category,
+            } => changes.extend(self.add_operation(
+                OperationType::Deposit,
+                amount,
+                description,
+                category,
+            )?),
+            Subcommand::Status {
+                ref filter,
+                ref criteria,
+                sort,
+                reverse,
+                top,
+                check_target,
+            } => {
+                let filter = criteria.to_filter(filter.as_deref())?;
+                let sorting = Sorting {
+                    key: sort.map(Into::into),
+                    reverse,
+                    top,
+                };
+
+                let bookkeeper = self.load_bookkeeper()?;
+                if filter.is_empty() {
+                    bookkeeper.display_status(&self.config, &sorting);
+                } else {
+                    let month = BookkeepingFile::current_file();
+                    let mut results = self.search(&filter, Some(month), Some(month))?;
+                    results.sort(&sorting);
+                    self.display_results(&results);
+                }
+
+                // The whole month counts, whatever was filtered out of what's shown
+                if check_target {
+                    bookkeeper.check_target()?;
+                }
+            }
+            Subcommand::Search {
+                ref pattern,
+                ref criteria,
+                from,
+                to,
+            } => {
+                let results = self.search(&criteria.to_filter(pattern.as_deref())?, from, to)?;
+                self.display_results(&results);
+            }
+            Subcommand::Query { ref query } => {
+                let query = Query::parse(query)?;
+                let mut storage =
+                    storage::open(self.dirs.data(), Vault::unlock(self.dirs.data())?)?;
+                let results = query.run(storage.as_mut())?;
+
+                match self.config.output {
+                    OutputFormat::Table => results.display(&self.config),
+                    OutputFormat::Json => println!("{:#}", results.to_json()),
+                }
+            }
+            Subcommand::Report {
+                period: ReportPeriod::Year { year },
+            } => {
+                let mut storage =
+                    storage::open(self.dirs.data(), Vault::unlock(self.dirs.data())?)?;
+                let report = YearReport::load(storage.as_mut(), year)?;
+
+                if report.months.is_empty() {
+                    log::warn!("no month of {year} is in the books");
+                }
+
+                match self.config.output {
+                    OutputFormat::Table => report.display(&self.config),
+                    OutputFormat::Json => println!("{:#}", report.to_json()),
+                }
+            }
+            Subcommand::Check { fix } => {
+                if Backend::in_use(self.dirs.data()) == Backend::Sqlite {
+                    log::info!("the books are stored in SQLite, only TOML files are checked");
+                }
+
+                let vault = Vault::unlock(self.dirs.data())?;
+                let report = check_folder(self.dirs.data(), fix, &vault)?;
+                report.display();
+
+                if report.is_ok().not() {
+                    return Err(Error::CheckFailed(report.problems.len()));
+                }
+            }
+            Subcommand::Migrate { to } => {
+                let to = Backend::from(to);
+                let vault = Vault::unlock(self.dirs.data())?;
+                let moved = storage::migrate_books(self.dirs.data(), to, vault)?;
+                println!("Moved {moved} month(s) to {}", to.name());
             }
-            Subcommand::Put { amount, ref description } => {
-                let operation = Operation::new(day, OperationType::Deposit, amount, description);
-                bookkeeper.add_operation(operation)?;
+            Subcommand::Encrypt => {
+                let encrypted = vault::encrypt_books(self.dirs.data())?;
+                println!("Encrypted {encrypted} file(s)");
             }
-            Subcommand::Status => {
-                bookkeeper.display_status();
+            Subcommand::Decrypt => {
+                let decrypted = vault::decrypt_books(self.dirs.data())?;
+                println!("Decrypted {decrypted} file(s)");
+            }
+            Subcommand::Config { ref action } => match action {
+                ConfigAction::Get { key } => {
+                    println!("{}", self.config.get(self.dirs.config(), key)?);
+                }
+                ConfigAction::Set { key, value } => Config::set(self.dirs.config(), key, value)?,
+                ConfigAction::List => print!("{}", self.config.list()),
+            },
+            Subcommand::Log { month, limit } => {
+                let history = GitHistory::find(self.dirs.data())?
+                    .ok_or_else(|| Error::NoHistory(self.dirs.data().to_owned()))?;
+
+                print!("{}", history.log(month.as_ref(), limit)?);
+            }
+            Subcommand::Payees => {
+                let index = self.load_bookkeeper()?.payees()?;
+                let payees = index.payees();
+
+                match self.config.output {
+                    OutputFormat::Table => {
+                        for payee in payees {
+                            let others: Vec<_> = payee
+                                .spellings()
+                                .filter(|spelling| *spelling != payee.canonical())
+                                .collect();
+
+                            print!("{:>5}  {}", payee.uses(), payee.canonical());
+                            if others.is_empty().not() {
+                                print!(" (also {})", others.join(", "));
+                            }
+                            println!();
+                        }
+                    }
+                    OutputFormat::Json => {
+                        let payees: Vec<_> = payees.iter().map(|payee| payee.to_json()).collect();
+                        println!("{:#}", serde_json::Value::from(payees));
+                    }
+                }
+            }
+            Subcommand::Completions { shell } => {
+                shell_scripts::print_script(shell, Opts::command())?
+            }
+            Subcommand::Man { ref command } => man::print_page(Opts::command(), command)?,
+            Subcommand::Tui => tui::run(self.dirs.data(), &self.config, changes)?,
+            Subcommand::Complete { kind } => {
+                let candidates = match kind {
+                    CompletionKind::Descriptions => {
+                        Candidates::gather(self.dirs.data(), &self.config)?.descriptions
+                    }
+                    CompletionKind::Categories => {
+                        Candidates::gather(self.dirs.data(), &self.config)?.categories
+                    }
+                    CompletionKind::Profiles => Dirs::profile_names(self.data_dir.clone())?
+                        .into_iter()
+                        .collect(),
+                };
+
+                for candidate in candidates {
+                    println!("{candidate}");
+                }
             }
         };
 
         Ok(())
     }
+
+    /// Adds an operation to the current month, asking for its details if not given
+    ///
+    /// Descriptions that look like one already in the books are changed to that one
+    /// if the settings say so, or suggested when asking for them.
+    fn add_operation(
+        &self,
+        kind: OperationType,
+        amount: &Option<BigDecimal>,
+        description: &Option<String>,
+        category: &Option<String>,
+    ) -> Result<Option<String>> {
+        let month = BookkeepingFile::current_file();
+        let today = Local::today().day() as u8;
+
+        // The passphrase is asked for once, and the month is only locked after
+        // the questions are answered. The payees are only indexed when they're
+        // needed, out of the latest months
+        let vault = Vault::unlock(self.dirs.data())?;
+        let payees = || {
+            let mut storage = storage::open(self.dirs.data(), vault.clone())?;
+            PayeeIndex::from_recent_months(storage.as_mut(), PayeeIndex::RECENT_MONTHS)
+        };
+
+        let operation = match (amount, description) {
+            (Some(amount), Some(description)) => {
+                let mut operation = Operation::new(today, kind, amount.clone(), description);
+                operation.category = self.checked_category(category)?;
+                if self.config.normalize_descriptions {
+                    self.spell_as_usual(&payees()?, &mut operation);
+                }
+                operation
+            }
+            _ if io::stdin().is_terminal().not() => return Err(Error::MissingOperation),
+            _ => {
+                let category = self.checked_category(category)?;
+                let stdin = io::stdin();
+                let index = payees()?;
+                let mut wizard = Wizard::new(stdin.lock(), io::stderr(), &self.config, &index);
+                match wizard.ask(kind, &month, today, category)? {
+                    Some(operation) => operation,
+                    None => {
+                        log::info!("nothing was added");
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+
+        // Described as stored, after being asked for and spelled as usual
+        let summary = format!("{} ({})", operation.summary(), month.as_str());
+        let storage = storage::open(self.dirs.data(), vault)?;
+        Bookkeeper::load(storage, month, Access::ReadWrite)?.add_operation(operation)?;
+
+        Ok(Some(summary))
+    }
+
+    /// Changes the description of `operation` to the usual spelling of one already
+    /// in the books, if it looks like one
+    fn spell_as_usual(&self, index: &PayeeIndex, operation: &mut Operation) {
+        if let Some(usual) = index.suggest(&operation.description) {
+            log::info!(
+                "recorded \"{}\" as \"{usual}\", its usual spelling",
+                operation.description
+            );
+            operation.description = usual.to_owned();
+        }
+    }
+
+    fn search(
+        &self,
+        filter: &Filter,
+        from: Option<BookkeepingFile>,
+        to: Option<BookkeepingFile>,
+    ) -> Result<SearchResults> {
+        let mut storage = storage::open(self.dirs.data(), Vault::unlock(self.dirs.data())?)?;
+
+        SearchResults::search(storage.as_mut(), filter, from, to)
+    }
+
+    fn display_results(&self, results: &SearchResults) {
+        match self.config.output {
+            OutputFormat::Table => results.display(&self.config),
+            OutputFormat::Json => println!("{:#}", results.to_json()),
+        }
+    }
+
+    /// `category`, if it's one of the categories in the settings
+    fn checked_category(&self, category: &Option<String>) -> Result<Option<String>> {
+        if let Some(category) = category {
+            self.config.check_category(category)?;
+        }
+
+        Ok(category.clone())
+    }
+}
+
+/// Stands in for the terminal interface when porquinho is built without the `tui`
+/// feature, which leaves out its dependencies
+#[cfg(not(feature = "tui"))]
+mod tui {
+    use std::path::Path;
+
+    use porquinho::{config::Config, Error, Result};
+
+    pub fn run(_folder: &Path, _config: &Config, _changes: &mut Vec<String>) -> Result<()> {
+        Err(Error::TuiUnsupported)
+    }
 }
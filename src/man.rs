@@ -0,0 +1,122 @@
+use std::{
+    io::{self, Write},
+    ops::Not,
+};
+
+use clap::Command;
+use clap_mangen::Man;
+use porquinho::error::{Error, ErrorClass};
+use roff::{bold, roman, Roff};
+
+/// Prints the manual page of porquinho, or of the command at `path` if given,
+/// made out of the same definitions as `--help`
+///
+/// The pages of commands are named like `porquinho-take`, which is how the
+/// list of commands of each page refers to them.
+pub fn print_page(command: Command, path: &[String]) -> porquinho::Result<()> {
+    let page = page(command, path)?;
+    io::stdout().lock().write_all(&page)?;
+
+    Ok(())
+}
+
+fn page(mut command: Command, path: &[String]) -> porquinho::Result<Vec<u8>> {
+    // Gives every command the global options and the `--help` flag
+    command.build();
+
+    let mut name = command.get_name().to_owned();
+    for (depth, subcommand) in path.iter().enumerate() {
+        command = command
+            .find_subcommand(subcommand)
+            .filter(|found| found.is_hide_set().not())
+            .ok_or_else(|| Error::UnknownCommand(path[..=depth].join(" ")))?
+            .clone();
+        name = format!("{name}-{}", command.get_name());
+    }
+
+    let man = Man::new(command.clone().name(name));
+    let mut page = vec![];
+
+    man.render_title(&mut page)?;
+    man.render_name_section(&mut page)?;
+    man.render_synopsis_section(&mut page)?;
+    man.render_description_section(&mut page)?;
+    if command.get_arguments().any(|arg| arg.is_hide_set().not()) {
+        man.render_options_section(&mut page)?;
+    }
+    if command.has_subcommands() {
+        man.render_subcommands_section(&mut page)?;
+    }
+    page.extend(exit_status().to_roff().as_bytes());
+    if command.get_version().is_some() {
+        man.render_version_section(&mut page)?;
+    }
+
+    Ok(page)
+}
+
+/// The section listing the codes porquinho exits with, which clap knows nothing about
+fn exit_status() -> Roff {
+    let mut roff = Roff::new();
+
+    roff.control("SH", ["EXIT STATUS"]);
+    roff.control("TP", [])
+        .text([bold("0")])
+        .text([roman("Success")]);
+    for class in ErrorClass::ALL {
+        roff.control("TP", [])
+            .text([bold(class.exit_code().to_string())])
+            .text([roman(class.description())]);
+    }
+
+    roff
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Not;
+
+    use clap::CommandFactory;
+
+    use super::page;
+    use crate::cli::Opts;
+
+    fn page_of(path: &[&str]) -> String {
+        let path: Vec<_> = path.iter().map(|name| name.to_string()).collect();
+        String::from_utf8(page(Opts::command(), &path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn documents_every_visible_command() {
+        let page = page_of(&[]);
+
+        for section in [
+            "NAME",
+            "SYNOPSIS",
+            "OPTIONS",
+            "SUBCOMMANDS",
+            "\"EXIT STATUS\"",
+        ] {
+            assert!(page.contains(&format!(".SH {section}\n")), "{section}");
+        }
+        assert!(page.contains("porquinho\\-take(1)"));
+        assert!(page.contains("PORQUINHO_DATA_DIR"));
+        assert!(page.contains("porquinho\\-complete").not());
+    }
+
+    #[test]
+    fn prints_the_pages_of_commands() {
+        let take = page_of(&["take"]);
+        assert!(take.contains(".TH porquinho-take 1"));
+        assert!(take.contains("\\-\\-category"));
+        // Global options are documented in the page of every command
+        assert!(take.contains("\\-\\-profile"));
+
+        let config = page_of(&["config"]);
+        assert!(config.contains("porquinho\\-config\\-set(1)"));
+        assert!(page_of(&["config", "set"]).contains(".TH porquinho-config-set 1"));
+
+        let path = ["complete".to_owned()];
+        assert!(page(Opts::command(), &path).is_err());
+    }
+}
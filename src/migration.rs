@@ -0,0 +1,314 @@
+use std::str::FromStr;
+
+use toml_edit::{ArrayOfTables, Document, Item, Value};
+
+use crate::{
+    diagnostic::SourceFile,
+    file::sibling_path,
+    parser::{Operation, OperationType},
+    storage::editor::{comment_lines, loose_comments, operation_record, take_entries},
+    vault::Vault,
+    Error, Result,
+};
+
+/// Key of bookkeeping files holding the version of the format they're written in
+pub const FORMAT_VERSION_KEY: &str = "format_version";
+
+/// Version of the bookkeeping file format written by this build of porquinho
+pub const CURRENT_FORMAT_VERSION: i64 = 2;
+
+/// An upgrade of bookkeeping files from one version of the format to the next one
+struct Migration {
+    /// The version upgraded from, the result is in version `from + 1`
+    from: i64,
+    /// Changes the document, using the source file only to point out problems
+    apply: fn(&mut Document, &SourceFile) -> Result<()>,
+}
+
+/// Every migration, in order
+///
+/// Files without a version are in version 0, the original format.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        // Version 1 only adds the version key itself
+        apply: |_, _| Ok(()),
+    },
+    Migration {
+        from: 1,
+        apply: structure_operations,
+    },
+];
+
+/// The version of the format `contents` is written in, or `None` if it isn't valid TOML
+///
+/// Errs if the version is newer than this build of porquinho understands.
+pub fn format_version(source: &SourceFile) -> Result<Option<i64>> {
+    let document: Document = match source.contents.parse() {
+        Ok(document) => document,
+        // Reported by whoever parses the file next
+        Err(_) => return Ok(None),
+    };
+
+    let version = match document.get(FORMAT_VERSION_KEY) {
+        Some(version) => version.as_integer().ok_or_else(|| {
+            source.diagnose_key(
+                FORMAT_VERSION_KEY,
+                format!("'{FORMAT_VERSION_KEY}' must be an integer"),
+                &format!("the current version is {CURRENT_FORMAT_VERSION}"),
+            )
+        })?,
+        None => 0,
+    };
+
+    if version > CURRENT_FORMAT_VERSION {
+        return Err(Error::UnsupportedFormatVersion {
+            path: source.path.to_owned(),
+            version,
+        });
+    }
+
+    Ok(Some(version))
+}
+
+/// Whether the bookkeeping file in `source` must be migrated before being used
+pub fn is_outdated(source: &SourceFile) -> Result<bool> {
+    if source.contents.trim().is_empty() {
+        // Empty files are created in the current version once written to
+        return Ok(false);
+    }
+
+    let version = format_version(source)?;
+
+    Ok(version.is_some_and(|version| version < CURRENT_FORMAT_VERSION))
+}
+
+/// Upgrades the bookkeeping file in `source` to the current version of the format,
+/// returning its new contents
+///
+/// A copy of the file in its previous version is kept as `<name>.v<version>.bak`.
+/// The file must be locked by the caller.
+pub fn migrate_file(source: &SourceFile, vault: &Vault) -> Result<String> {
+    let version = match format_version(source)? {
+        Some(version) if version < CURRENT_FORMAT_VERSION => version,
+        _ => return Ok(source.contents.to_owned()),
+    };
+
+    let migrated = migrate(source, version)?;
+
+    let backup_path = sibling_path(source.path, &format!("v{version}.bak"));
+    vault.write(&backup_path, source.contents)?;
+    vault.write_atomically(source.path, &migrated)?;
+    log::info!(
+        "upgraded {} to version {CURRENT_FORMAT_VERSION} of the bookkeeping format, \
+         the previous version was kept in {}",
+        source.path.display(),
+        backup_path.display()
+    );
+
+    Ok(migrated)
+}
+
+/// Applies every migration needed to upgrade the contents of `source` from `version`,
+/// without writing them anywhere
+pub fn migrate(source: &SourceFile, version: i64) -> Result<String> {
+    let contents = source.contents;
+    let mut document: Document = contents.parse()?;
+    if document.get(FORMAT_VERSION_KEY).is_none() {
+        // Files in version 0 usually have no version key, which is placed at their top
+        document = format!("{FORMAT_VERSION_KEY} = {version}\n{contents}").parse()?;
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.from >= version)
+    {
+        (migration.apply)(&mut document, source)?;
+        document[FORMAT_VERSION_KEY] = toml_edit::value(migration.from + 1);
+    }
+
+    Ok(document.to_string())
+}
+
+/// Version 2 stores each operation as a record with named fields, in an array of
+/// tables, instead of packing it into a `"<day> <+ or -> <amount> <description>"` string
+///
+/// The sign of each operation decides which array it ends up in, and the comments
+/// around it are placed above its record.
+fn structure_operations(document: &mut Document, source: &SourceFile) -> Result<()> {
+    let mut records = vec![];
+
+    for kind in [OperationType::Withdraw, OperationType::Deposit] {
+        let (array_key, _) = kind.name_and_symbol();
+
+        let array = match document.get(array_key) {
+            Some(item) => item
+                .as_array()
+                .filter(|array| array.iter().all(Value::is_str))
+                .ok_or_else(|| {
+                    source.diagnose_key(
+                        array_key,
+                        format!("'{array_key}' must be an array of operations"),
+                        "operations are written as '<day> <+ or -> <amount> <description>', \
+                         e.g. '22 - 5.00 Lunch'",
+                    )
+                })?,
+            None => continue,
+        };
+
+        // Comments about the array as a whole go above its first record
+        let mut array_comments: Vec<String> = document
+            .key_decor(array_key)
+            .and_then(|decor| decor.prefix())
+            .map(|prefix| comment_lines(prefix).collect())
+            .unwrap_or_default();
+        array_comments.extend(loose_comments(array));
+        array_comments.extend(
+            array
+                .decor()
+                .suffix()
+                .map(comment_lines)
+                .into_iter()
+                .flatten(),
+        );
+
+        for entry in take_entries(array) {
+            let operation = Operation::from_str(&entry.text)
+                .map_err(|err| source.diagnose_operation(&entry.text, &err))?;
+
+            let comments = std::mem::take(&mut array_comments)
+                .into_iter()
+                .chain(entry.comments());
+            records.push((operation, comments.collect::<Vec<_>>()));
+        }
+
+        // Operations-less arrays keep their comments at the end of the file
+        for comment in array_comments {
+            let trailing = format!("{}{comment}\n", document.trailing());
+            document.set_trailing(&trailing);
+        }
+
+        document.remove(array_key);
+    }
+
+    for (operation, comments) in records {
+        let (array_key, _) = operation.kind.name_and_symbol();
+
+        let mut record = operation_record(&operation);
+        let comments: String = comments.iter().map(|line| format!("{line}\n")).collect();
+        record.decor_mut().set_prefix(format!("\n{comments}"));
+
+        let item = document
+            .entry(array_key)
+            .or_insert_with(|| Item::ArrayOfTables(ArrayOfTables::new()));
+        // Safety: only arrays of tables are inserted under these keys above
+        item.as_array_of_tables_mut().unwrap().push(record);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Not, path::Path};
+
+    use fs_err as fs;
+
+    use super::{is_outdated, migrate, migrate_file, CURRENT_FORMAT_VERSION};
+    use crate::{diagnostic::SourceFile, vault::Vault, Error};
+
+    #[test]
+    fn upgrades_files_without_a_version_keeping_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("10-2024");
+        let contents = "# October\ntake = [\"3 - 12.00 Lunch\"]\nput = []\n";
+        fs::write(&path, contents).unwrap();
+
+        let source = SourceFile::new(&path, contents);
+        assert!(is_outdated(&source).unwrap());
+
+        let migrated = migrate_file(&source, &Vault::default()).unwrap();
+        assert_eq!(
+            migrated,
+            format!(
+                "format_version = {CURRENT_FORMAT_VERSION}\n\n# October\n[[take]]\nday = 3\namount = \"12.00\"\ndescription = \"Lunch\"\n"
+            )
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), migrated);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("10-2024.v0.bak")).unwrap(),
+            contents
+        );
+
+        assert!(is_outdated(&SourceFile::new(&path, &migrated))
+            .unwrap()
+            .not());
+    }
+
+    #[test]
+    fn upgrades_files_with_an_explicit_version_0() {
+        let contents = "format_version = 0\ntake = [\"3 - 12.00 Lunch\"]\n";
+        let source = SourceFile::new(Path::new("10-2024"), contents);
+
+        assert_eq!(
+            migrate(&source, 0).unwrap(),
+            format!(
+                "format_version = {CURRENT_FORMAT_VERSION}\n\n[[take]]\nday = 3\namount = \"12.00\"\ndescription = \"Lunch\"\n"
+            )
+        );
+    }
+
+    #[test]
+    fn turns_operation_strings_into_records_keeping_their_comments() {
+        let contents = r#"format_version = 1
+target = 3000
+take = [
+    # Weekdays
+    "3 - 12.00 Lunch", # with Ana
+    "4 + 50 Refund",
+]
+put = [] # nothing yet
+"#;
+        let source = SourceFile::new(Path::new("10-2024"), contents);
+
+        let expected = r#"format_version = 2
+target = 3000
+
+# Weekdays
+# with Ana
+[[take]]
+day = 3
+amount = "12.00"
+description = "Lunch"
+
+[[put]]
+day = 4
+amount = "50"
+description = "Refund"
+# nothing yet
+"#;
+        assert_eq!(migrate(&source, 1).unwrap(), expected);
+    }
+
+    #[test]
+    fn points_at_operations_that_cant_be_migrated() {
+        let contents = "format_version = 1\ntake = [\"3 - 1,00 Lunch\"]\n";
+        let source = SourceFile::new(Path::new("10-2024"), contents);
+
+        match migrate(&source, 1) {
+            Err(Error::Diagnostic(diagnostic)) => assert_eq!(diagnostic.line, 2),
+            other => panic!("expected a diagnostic, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn refuses_files_newer_than_supported() {
+        let contents = "format_version = 999\ntake = []\nput = []\n";
+        let source = SourceFile::new(Path::new("10-2024"), contents);
+
+        assert!(matches!(
+            is_outdated(&source),
+            Err(Error::UnsupportedFormatVersion { version: 999, .. })
+        ));
+    }
+}
@@ -1,7 +1,9 @@
 use std::cmp::Ordering;
-use std::{ops::Not, str::FromStr};
+use std::str::FromStr;
 
 use bigdecimal::BigDecimal;
+use chrono::{NaiveTime, Timelike};
+use toml::value::{Table as TomlTable, Value as TomlValue};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OperationType {
@@ -52,10 +54,47 @@ pub enum ParseError {
     NoDescription(String),
     #[error("Malformed operation: '{0}'")]
     Malformed(String),
+    #[error("Operation has no '{0}'")]
+    MissingField(&'static str),
+    #[error("'{value}' is not a valid {field}")]
+    InvalidField { field: &'static str, value: String },
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(test, derive(PartialEq))]
+impl ParseError {
+    /// The part of the input that could not be parsed
+    pub fn offending_text(&self) -> &str {
+        match self {
+            Self::InvalidOperationType(text)
+            | Self::InvalidDay(text)
+            | Self::InvalidDecimal(text)
+            | Self::NoDescription(text)
+            | Self::Malformed(text)
+            | Self::InvalidField { value: text, .. } => text,
+            // Points at the whole operation
+            Self::MissingField(_) => "",
+        }
+    }
+
+    /// A suggestion on how to fix the input
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Self::InvalidOperationType(_) => "use '+' for deposits and '-' for withdraws",
+            Self::InvalidDay(_) => "the day must be a number between 1 and 31",
+            Self::InvalidDecimal(_) => "amounts use a dot as the decimal separator, e.g. 42.10",
+            Self::NoDescription(_) => "every operation needs a description after its amount",
+            Self::Malformed(_) => {
+                "operations are written as '<day> <+ or -> <amount> <description>', e.g. '22 - 5.00 Lunch'"
+            }
+            Self::MissingField(_) => "every operation needs a day, an amount and a description",
+            Self::InvalidField { field: "time", .. } => {
+                "times are written as \"HH:MM\" or \"HH:MM:SS\", e.g. \"12:30\""
+            }
+            Self::InvalidField { .. } => "this field must be a string",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Operation {
     pub day: u8,
     pub kind: OperationType,
@@ -63,8 +102,26 @@ pub struct Operation {
     // TODO: rename to account?
     // TODO: make it optional?
     pub description: String,
+    /// Time of the day when the operation happened
+    pub time: Option<NaiveTime>,
+    pub category: Option<String>,
+    /// Currency of the amount, e.g. "USD", if not the default one
+    pub currency: Option<String>,
+    /// Identifier of the operation, e.g. the one given to it by the bank
+    pub id: Option<String>,
 }
 
+/// Fields of the records that operations are stored as, in the order they are written
+pub const RECORD_FIELDS: [&str; 7] = [
+    "day",
+    "amount",
+    "description",
+    "time",
+    "category",
+    "currency",
+    "id",
+];
+
 impl Operation {
     pub fn new(
         day: u8,
@@ -77,19 +134,122 @@ impl Operation {
             kind,
             amount,
             description: description.to_string(),
+            time: None,
+            category: None,
+            currency: None,
+            id: None,
         }
     }
 
-    pub fn from_str(input: &str) -> ParseResult<Self> {
+    /// A short description of the operation, e.g. `take 42.10 Lunch`
+    pub fn summary(&self) -> String {
+        let (name, _) = self.kind.name_and_symbol();
+
+        format!("{name} {} {}", self.amount, self.description)
+    }
+
+    /// Parses an operation stored as a record of named fields, such as
+    ///
+    /// ```toml
+    /// [[take]]
+    /// day = 22
+    /// amount = "5.00"
+    /// description = "Lunch"
+    /// category = "food"
+    /// ```
+    ///
+    /// Amounts are written as strings so that no precision is lost, but plain
+    /// TOML numbers are accepted too.
+    pub fn from_record(kind: OperationType, record: &TomlTable) -> ParseResult<Self> {
+        let day = match record.get("day") {
+            Some(TomlValue::Integer(day)) => {
+                u8::try_from(*day).map_err(|_| ParseError::InvalidDay(day.to_string()))?
+            }
+            Some(other) => return Err(ParseError::InvalidDay(other.to_string())),
+            None => return Err(ParseError::MissingField("day")),
+        };
+
+        let amount = match record.get("amount") {
+            Some(TomlValue::String(amount)) => BigDecimal::from_str(amount.trim())
+                .map_err(|_| ParseError::InvalidDecimal(amount.clone()))?,
+            Some(TomlValue::Integer(amount)) => BigDecimal::from(*amount),
+            Some(TomlValue::Float(amount)) => BigDecimal::from_str(&amount.to_string())
+                .map_err(|_| ParseError::InvalidDecimal(amount.to_string()))?,
+            Some(other) => return Err(ParseError::InvalidDecimal(other.to_string())),
+            None => return Err(ParseError::MissingField("amount")),
+        };
+
+        let description = match record.get("description") {
+            Some(TomlValue::String(description)) if !description.trim().is_empty() => {
+                parse_description(description)
+            }
+            Some(other) => return Err(ParseError::NoDescription(other.to_string())),
+            None => return Err(ParseError::MissingField("description")),
+        };
+
+        let mut operation = Self::new(day, kind, amount, description);
+
+        operation.time = optional_field(record, "time")?
+            .map(|time| {
+                parse_time(time).ok_or_else(|| ParseError::InvalidField {
+                    field: "time",
+                    value: time.to_owned(),
+                })
+            })
+            .transpose()?;
+        operation.category = optional_field(record, "category")?.map(Into::into);
+        operation.currency = optional_field(record, "currency")?.map(Into::into);
+        operation.id = optional_field(record, "id")?.map(Into::into);
+
+        Ok(operation)
+    }
+}
+
+/// Parses an operation in the single line format of version 1 of the bookkeeping format,
+/// e.g. `22 - 5.00 Lunch`
+impl FromStr for Operation {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> ParseResult<Self> {
         let (day, rest) = parse_day(input)?;
 
         let (kind, rest) = parse_operation_type(rest)?;
 
         let (amount, rest) = parse_decimal(rest)?;
 
-        let description = parse_description(rest).into();
+        let description = parse_description(rest);
+
+        Ok(Self::new(day, kind, amount, description))
+    }
+}
+
+/// Reads a field of a record that, if present, must be a string
+fn optional_field<'a>(record: &'a TomlTable, field: &'static str) -> ParseResult<Option<&'a str>> {
+    match record.get(field) {
+        Some(TomlValue::String(value)) => Ok(Some(value)),
+        Some(other) => Err(ParseError::InvalidField {
+            field,
+            value: other.to_string(),
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Parses a time of the day written as `HH:MM` or `HH:MM:SS`
+pub fn parse_time(input: &str) -> Option<NaiveTime> {
+    let input = input.trim();
 
-        Ok(Self { day, kind, amount, description })
+    NaiveTime::parse_from_str(input, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M:%S"))
+        .ok()
+}
+
+/// Writes a time of the day as `HH:MM`, or as `HH:MM:SS` if it has seconds
+pub fn format_time(time: NaiveTime) -> String {
+    if time.second() == 0 {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%H:%M:%S").to_string()
     }
 }
 
@@ -108,17 +268,18 @@ fn parse_day(input: &str) -> ParseResult<(u8, &str)> {
 }
 
 fn parse_operation_type(input: &str) -> ParseResult<(OperationType, &str)> {
-    // Assumes input is trimmed
-    debug_assert!(input == input.trim_start());
-    // Assumes input is non-empty
-    debug_assert!(input.is_empty().not());
+    let input = input.trim_start();
 
-    let (first, rest) = input.split_at(1);
+    let mut chars = input.chars();
+    let first = chars
+        .next()
+        .ok_or_else(|| ParseError::Malformed(input.to_owned()))?;
+    let rest = chars.as_str();
 
     match first {
-        "+" => Ok((OperationType::Deposit, rest)),
-        "-" => Ok((OperationType::Withdraw, rest)),
-        _ => Err(ParseError::InvalidOperationType(first.to_owned())),
+        '+' => Ok((OperationType::Deposit, rest)),
+        '-' => Ok((OperationType::Withdraw, rest)),
+        _ => Err(ParseError::InvalidOperationType(first.to_string())),
     }
 }
 
@@ -157,6 +318,16 @@ mod operation_parsing {
     use super::Operation;
     use crate::parser::{parse_decimal, parse_description, OperationType, ParseError};
 
+    #[test]
+    fn summarizes_operations() {
+        let amount = BigDecimal::from_str("42.10").unwrap();
+        let lunch = Operation::new(3, OperationType::Withdraw, amount, "Lunch");
+        assert_eq!(lunch.summary(), "take 42.10 Lunch");
+
+        let salary = Operation::new(5, OperationType::Deposit, BigDecimal::from(900), "Salary");
+        assert_eq!(salary.summary(), "put 900 Salary");
+    }
+
     #[test]
     fn parses_operations_correctly() {
         let five = BigDecimal::from_str("5.00").unwrap();
@@ -164,22 +335,12 @@ mod operation_parsing {
 
         assert_eq!(
             Operation::from_str("22 + 5.00 Salary").unwrap(),
-            Operation {
-                day: 22,
-                kind: OperationType::Deposit,
-                amount: five,
-                description: "Salary".into()
-            }
+            Operation::new(22, OperationType::Deposit, five, "Salary")
         );
 
         assert_eq!(
             Operation::from_str("12 - 6.000 Rent\n").unwrap(),
-            Operation {
-                day: 12,
-                kind: OperationType::Withdraw,
-                amount: six,
-                description: "Rent".into()
-            }
+            Operation::new(12, OperationType::Withdraw, six, "Rent")
         );
     }
 
@@ -227,6 +388,66 @@ mod operation_parsing {
         );
     }
 
+    #[test]
+    fn errs_instead_of_panicking_on_unexpected_input() {
+        assert_eq!(
+            Operation::from_str("3 é 5.00 Lunch").unwrap_err(),
+            ParseError::InvalidOperationType("é".to_owned())
+        );
+
+        assert_eq!(
+            Operation::from_str("3   - 5.00 Lunch").unwrap().kind,
+            OperationType::Withdraw
+        );
+
+        assert_eq!(
+            Operation::from_str("3x - 5.00 Lunch").unwrap_err(),
+            ParseError::InvalidDay("3x".to_owned())
+        );
+    }
+
+    #[test]
+    fn parses_records_with_optional_fields() {
+        let record: toml::value::Table = toml::from_str(
+            "day = 3\namount = \"12.50\"\ndescription = \" Lunch\"\ntime = \"12:30\"\ncategory = \"food\"\n",
+        )
+        .unwrap();
+
+        let operation = Operation::from_record(OperationType::Withdraw, &record).unwrap();
+
+        let mut expected = Operation::new(
+            3,
+            OperationType::Withdraw,
+            BigDecimal::from_str("12.50").unwrap(),
+            "Lunch",
+        );
+        expected.time = chrono::NaiveTime::from_hms_opt(12, 30, 0);
+        expected.category = Some("food".into());
+        assert_eq!(operation, expected);
+    }
+
+    #[test]
+    fn errs_on_incomplete_or_mistyped_records() {
+        let record = |text: &str| toml::from_str::<toml::value::Table>(text).unwrap();
+        let parse = |text: &str| Operation::from_record(OperationType::Deposit, &record(text));
+
+        assert_eq!(
+            parse("day = 3\ndescription = \"Salary\"").unwrap_err(),
+            ParseError::MissingField("amount")
+        );
+        assert_eq!(
+            parse("day = 3\namount = 5\ndescription = \"Salary\"\ntime = \"noon\"").unwrap_err(),
+            ParseError::InvalidField {
+                field: "time",
+                value: "noon".into()
+            }
+        );
+        assert_eq!(
+            parse("day = 3\namount = \"5,00\"\ndescription = \"Salary\"").unwrap_err(),
+            ParseError::InvalidDecimal("5,00".into())
+        );
+    }
+
     #[test]
     fn parses_descriptions_correctly() {
         assert_eq!("Petrobrás", parse_description("  Petrobrás"));
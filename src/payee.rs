@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::{file::BookkeepingFile, storage::Storage, Access, Result};
+
+/// How alike two descriptions must be, from 0 to 1, to be taken for the same
+/// payee, e.g. "Alugel" is 0.86 alike to "Aluguel"
+const MIN_SIMILARITY: f64 = 0.75;
+
+/// The descriptions used in the books, grouped by payee, so that a new description
+/// can be checked against the spelling used before
+#[derive(Debug, Default)]
+pub struct PayeeIndex {
+    /// Payees by their descriptions in lowercase, with spaces collapsed
+    payees: BTreeMap<String, Payee>,
+}
+
+/// Everyone paid or paying under descriptions that differ only in case and spacing
+#[derive(Debug, Default)]
+pub struct Payee {
+    /// How many times each spelling was used
+    spellings: BTreeMap<String, usize>,
+    /// Category of the latest operation that had one
+    category: Option<String>,
+}
+
+impl Payee {
+    /// The spelling used the most, which is the one suggested
+    pub fn canonical(&self) -> &str {
+        // Ties go to the spelling that sorts first, which is the capitalized one.
+        // Safety: payees are only created along with a spelling
+        let (spelling, _) = self
+            .spellings
+            .iter()
+            .rev()
+            .max_by_key(|(_, uses)| **uses)
+            .unwrap();
+
+        spelling
+    }
+
+    /// How many operations were made with any of the spellings
+    pub fn uses(&self) -> usize {
+        self.spellings.values().sum()
+    }
+
+    /// Every spelling used, including the canonical one
+    pub fn spellings(&self) -> impl Iterator<Item = &str> {
+        self.spellings.keys().map(String::as_str)
+    }
+
+    /// The category the payee was last given
+    pub fn category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        json!({
+            "description": self.canonical(),
+            "uses": self.uses(),
+            "spellings": self.spellings().collect::<Vec<_>>(),
+            "category": self.category(),
+        })
+    }
+}
+
+impl PayeeIndex {
+    /// How many of the latest months are read to suggest descriptions, about a year
+    pub const RECENT_MONTHS: usize = 12;
+
+    /// Indexes the descriptions of every month kept in `storage`
+    ///
+    /// This reads all of the books, decrypting encrypted months along the way, so it
+    /// takes longer the more months there are. Build it once and share it.
+    pub fn from_storage(storage: &mut dyn Storage) -> Result<Self> {
+        let months = storage.list_months()?;
+
+        Self::from_months(storage, &months)
+    }
+
+    /// Indexes the descriptions of the latest `count` months kept in `storage`, which
+    /// is enough to suggest the descriptions in use without reading all of the books
+    pub fn from_recent_months(storage: &mut dyn Storage, count: usize) -> Result<Self> {
+        let months = storage.list_months()?;
+        let recent = &months[months.len().saturating_sub(count)..];
+
+        Self::from_months(storage, recent)
+    }
+
+    /// Indexes the descriptions of `months`, leaving their files as they are
+    fn from_months(storage: &mut dyn Storage, months: &[BookkeepingFile]) -> Result<Self> {
+        let mut index = Self::default();
+
+        for month in months {
+            for operation in storage.load_month(month, Access::Peek)?.operations {
+                index.add(&operation.description, operation.category.as_deref());
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Adds an operation described as `description`, which is newer than the ones already added
+    pub fn add(&mut self, description: &str, category: Option<&str>) {
+        let payee = self.payees.entry(key(description)).or_default();
+        *payee.spellings.entry(description.to_owned()).or_default() += 1;
+
+        if let Some(category) = category {
+            payee.category = Some(category.to_owned());
+        }
+    }
+
+    /// The payee `description` is one of the spellings of, ignoring case and spacing
+    pub fn find(&self, description: &str) -> Option<&Payee> {
+        self.payees.get(&key(description))
+    }
+
+    /// The categories the payees were last given
+    pub fn categories(&self) -> BTreeSet<&str> {
+        self.payees.values().filter_map(Payee::category).collect()
+    }
+
+    /// Every payee, most used first
+    pub fn payees(&self) -> Vec<&Payee> {
+        let mut payees: Vec<_> = self.payees.values().collect();
+        payees.sort_by(|a, b| {
+            b.uses()
+                .cmp(&a.uses())
+                .then(a.canonical().cmp(b.canonical()))
+        });
+
+        payees
+    }
+
+    /// The usual spelling of the payee `description` seems to be, if it's spelled differently
+    pub fn suggest(&self, description: &str) -> Option<&str> {
+        let key = key(description);
+
+        let payee = match self.find(description) {
+            Some(payee) => payee,
+            None => self
+                .payees
+                .iter()
+                .map(|(other, payee)| (strsim::normalized_damerau_levenshtein(&key, other), payee))
+                .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+                .max_by(|(a, a_payee), (b, b_payee)| {
+                    a.total_cmp(b).then(a_payee.uses().cmp(&b_payee.uses()))
+                })
+                .map(|(_, payee)| payee)?,
+        };
+
+        Some(payee.canonical()).filter(|canonical| *canonical != description)
+    }
+}
+
+/// What descriptions of the same payee have in common
+fn key(description: &str) -> String {
+    description
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PayeeIndex;
+    use crate::{parser::OperationType, storage::MemoryStorage};
+
+    fn index() -> PayeeIndex {
+        let mut index = PayeeIndex::default();
+        let operations = [
+            ("Uber", Some("transport")),
+            ("Uber", None),
+            ("uber", Some("work")),
+            ("iFood", Some("food")),
+            ("Aluguel", None),
+            ("Uber Eats", Some("food")),
+        ];
+        for (description, category) in operations {
+            index.add(description, category);
+        }
+
+        index
+    }
+
+    #[test]
+    fn suggests_the_usual_spelling() {
+        let index = index();
+
+        assert_eq!(index.suggest("UBER "), Some("Uber"));
+        assert_eq!(index.suggest("uber"), Some("Uber"));
+        assert_eq!(index.suggest("Alugel"), Some("Aluguel"));
+        assert_eq!(index.suggest("ifod"), Some("iFood"));
+
+        assert_eq!(index.suggest("Uber"), None);
+        assert_eq!(index.suggest("Uber Eats"), None);
+        assert_eq!(index.suggest("Padaria"), None);
+    }
+
+    #[test]
+    fn lists_the_most_used_payees_first() {
+        let index = index();
+        let payees = index.payees();
+
+        assert_eq!(payees[0].canonical(), "Uber");
+        assert_eq!(payees[0].uses(), 3);
+        assert_eq!(payees[0].spellings().collect::<Vec<_>>(), ["Uber", "uber"]);
+        assert_eq!(payees[0].category(), Some("work"));
+        assert_eq!(payees.len(), 4);
+
+        assert_eq!(index.find(" UBER").unwrap().canonical(), "Uber");
+        assert_eq!(
+            index.categories().into_iter().collect::<Vec<_>>(),
+            ["food", "work"]
+        );
+    }
+
+    #[test]
+    fn only_indexes_the_latest_months_when_asked_to() {
+        let mut storage = MemoryStorage::with_operations(&[
+            ("01-2025", 3, OperationType::Withdraw, 12, "Padaria"),
+            ("02-2025", 4, OperationType::Withdraw, 30, "Uber"),
+            ("03-2025", 5, OperationType::Withdraw, 50, "iFood"),
+        ]);
+
+        let recent = PayeeIndex::from_recent_months(&mut storage, 2).unwrap();
+        assert!(recent.find("Padaria").is_none());
+        assert!(recent.find("Uber").is_some());
+        assert!(recent.find("iFood").is_some());
+
+        let all = PayeeIndex::from_storage(&mut storage).unwrap();
+        assert!(all.find("Padaria").is_some());
+    }
+}
@@ -0,0 +1,888 @@
+use std::{cmp::Ordering, collections::BTreeMap, iter::Peekable, ops::Not, vec::IntoIter};
+
+use bigdecimal::BigDecimal;
+use nu_table::{draw_table, StyledString, Table, TextStyle};
+use serde_json::{json, Value as JsonValue};
+
+use crate::{
+    bookkeeper::status::table_header_from_column_names,
+    config::Config,
+    file::BookkeepingFile,
+    parser::{Operation, OperationType},
+    search::tags,
+    storage::Storage,
+    Access, Error, Result,
+};
+
+/// Decimal places that averages are rounded to
+const AVERAGE_DIGITS: i64 = 4;
+
+/// The average of `count` numbers adding up to `total`, where `count` isn't zero
+pub(crate) fn average(total: BigDecimal, count: usize) -> BigDecimal {
+    let average = total / BigDecimal::from(count as u64);
+
+    average.round(AVERAGE_DIGITS).normalized()
+}
+
+/// A question about the books, e.g.
+/// `take where category = food and month >= 2026-01 group by month sum amount`
+///
+/// It's made of, in this order and all optional:
+///
+/// - `take` or `put`, to only look at one kind of operation
+/// - `where` and conditions joined by `and`, e.g. `amount > 100` or `description ~ uber`
+/// - `group by` and a field, e.g. `group by category`
+/// - what to compute for each group, e.g. `count`, `sum amount` or `avg amount, max day`
+///
+/// Without grouping or computing anything, the matching operations are listed.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    kind: Option<OperationType>,
+    conditions: Vec<Condition>,
+    group_by: Option<Field>,
+    aggregates: Vec<Aggregate>,
+}
+
+/// What queries can look at in an operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Month,
+    Day,
+    Kind,
+    Amount,
+    Description,
+    Category,
+    /// The words starting with `#` in the description, see `search::tags`
+    Tag,
+}
+
+impl Field {
+    const ALL: [Self; 7] = [
+        Self::Month,
+        Self::Day,
+        Self::Kind,
+        Self::Amount,
+        Self::Description,
+        Self::Category,
+        Self::Tag,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Month => "month",
+            Self::Day => "day",
+            Self::Kind => "kind",
+            Self::Amount => "amount",
+            Self::Description => "description",
+            Self::Category => "category",
+            Self::Tag => "tag",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.name().eq_ignore_ascii_case(name))
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Self::Day | Self::Amount)
+    }
+
+    /// The number in this field of `operation`, if it's a numeric field
+    fn number(self, operation: &Operation) -> BigDecimal {
+        match self {
+            Self::Day => BigDecimal::from(operation.day),
+            _ => operation.amount.clone(),
+        }
+    }
+
+    /// What `operation`, kept in `month`, is grouped by, which are many or none
+    /// for tags
+    fn keys(self, month: &BookkeepingFile, operation: &Operation) -> Vec<Key> {
+        match self {
+            Self::Month => vec![Key::Month(month.year(), month.month())],
+            Self::Day => vec![Key::Day(operation.day)],
+            Self::Amount => vec![Key::Amount(operation.amount.clone())],
+            Self::Kind => vec![Key::Text(operation.kind.name_and_symbol().0.into())],
+            Self::Description => vec![Key::Text(operation.description.clone())],
+            Self::Category => vec![Key::Text(operation.category.clone().unwrap_or_default())],
+            Self::Tag => tags(&operation.description)
+                .map(|tag| Key::Text(tag.to_owned()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    /// Text containing the value, ignoring case
+    Contains,
+    NotContains,
+}
+
+impl Comparison {
+    const ALL: [Self; 8] = [
+        Self::Equal,
+        Self::NotEqual,
+        Self::Greater,
+        Self::GreaterOrEqual,
+        Self::Less,
+        Self::LessOrEqual,
+        Self::Contains,
+        Self::NotContains,
+    ];
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Contains => "~",
+            Self::NotContains => "!~",
+        }
+    }
+
+    fn is_negated(self) -> bool {
+        matches!(self, Self::NotEqual | Self::NotContains)
+    }
+
+    /// Whether a value that is `ordering` compared to the value of the condition satisfies it
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Equal => ordering.is_eq(),
+            Self::NotEqual => ordering.is_ne(),
+            Self::Greater => ordering.is_gt(),
+            Self::GreaterOrEqual => ordering.is_ge(),
+            Self::Less => ordering.is_lt(),
+            Self::LessOrEqual => ordering.is_le(),
+            Self::Contains | Self::NotContains => false,
+        }
+    }
+}
+
+/// What a field is compared to, read according to the field
+#[derive(Debug, Clone)]
+enum Value {
+    Month(i32, u32),
+    Number(BigDecimal),
+    Kind(OperationType),
+    /// In lowercase, since text is compared ignoring case
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    comparison: Comparison,
+    value: Value,
+}
+
+impl Condition {
+    fn holds(&self, month: &BookkeepingFile, operation: &Operation) -> bool {
+        let comparison = self.comparison;
+
+        match &self.value {
+            Value::Month(year, month_number) => {
+                comparison.holds((month.year(), month.month()).cmp(&(*year, *month_number)))
+            }
+            Value::Number(number) => comparison.holds(self.field.number(operation).cmp(number)),
+            Value::Kind(kind) => (operation.kind == *kind) != comparison.is_negated(),
+            Value::Text(text) => {
+                let texts: Vec<&str> = match self.field {
+                    Field::Description => vec![&operation.description],
+                    Field::Category => operation.category.as_deref().into_iter().collect(),
+                    _ => tags(&operation.description).collect(),
+                };
+
+                // Negated comparisons hold if no text matches, e.g. for any of the tags
+                let matches = texts.into_iter().any(|other| match comparison {
+                    Comparison::Equal | Comparison::NotEqual => other.to_lowercase() == *text,
+                    _ => other.to_lowercase().contains(text.as_str()),
+                });
+                matches != comparison.is_negated()
+            }
+        }
+    }
+}
+
+/// What is computed for each group of operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregate {
+    Count,
+    Sum(Field),
+    Average(Field),
+    Min(Field),
+    Max(Field),
+}
+
+impl Aggregate {
+    fn name(self) -> String {
+        let (function, field) = match self {
+            Self::Count => return "count".into(),
+            Self::Sum(field) => ("sum", field),
+            Self::Average(field) => ("avg", field),
+            Self::Min(field) => ("min", field),
+            Self::Max(field) => ("max", field),
+        };
+
+        format!("{function} {}", field.name())
+    }
+
+    fn compute(self, operations: &[&Operation]) -> Cell {
+        let numbers = |field: Field| operations.iter().map(move |op| field.number(op));
+
+        match self {
+            Self::Count => Cell::Count(operations.len()),
+            Self::Sum(field) => Cell::Number(numbers(field).sum()),
+            Self::Average(_) if operations.is_empty() => Cell::Empty,
+            Self::Average(field) => Cell::Number(average(numbers(field).sum(), operations.len())),
+            Self::Min(field) => numbers(field).min().map_or(Cell::Empty, Cell::Number),
+            Self::Max(field) => numbers(field).max().map_or(Cell::Empty, Cell::Number),
+        }
+    }
+}
+
+/// What groups are told apart by, ordered the way they're shown
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Month(i32, u32),
+    Day(u8),
+    Amount(BigDecimal),
+    Text(String),
+}
+
+impl Key {
+    fn into_cell(self) -> Cell {
+        match self {
+            Self::Month(year, month) => {
+                // Safety: only made from existing months
+                let month = BookkeepingFile::from_year_and_month(year, month).unwrap();
+                Cell::Text(month.as_str().to_owned())
+            }
+            Self::Day(day) => Cell::Count(day.into()),
+            Self::Amount(amount) => Cell::Number(amount),
+            Self::Text(text) => Cell::Text(text),
+        }
+    }
+}
+
+/// A value in the results of a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cell {
+    Text(String),
+    /// An amount, or what was computed from amounts or days
+    Number(BigDecimal),
+    Count(usize),
+    /// Nothing to compute from, e.g. the average of no operations
+    Empty,
+}
+
+impl Cell {
+    fn to_styled_string(&self, decimals: usize) -> StyledString {
+        match self {
+            Self::Text(text) => StyledString::new(text, TextStyle::basic_left()),
+            Self::Number(number) => {
+                StyledString::new(format!("{number:.decimals$}"), TextStyle::basic_right())
+            }
+            Self::Count(count) => StyledString::new(count, TextStyle::basic_right()),
+            Self::Empty => StyledString::new("", TextStyle::basic_left()),
+        }
+    }
+
+    /// The cell as JSON, with numbers as strings so that no precision is lost
+    fn to_json(&self) -> JsonValue {
+        match self {
+            Self::Text(text) => text.as_str().into(),
+            Self::Number(number) => number.to_string().into(),
+            Self::Count(count) => (*count).into(),
+            Self::Empty => JsonValue::Null,
+        }
+    }
+}
+
+/// What a query found, as a table
+#[derive(Debug)]
+pub struct QueryResults {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl QueryResults {
+    pub fn display(&self, config: &Config) {
+        let columns: Vec<_> = self.columns.iter().map(String::as_str).collect();
+        let header = table_header_from_column_names(&columns);
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.to_styled_string(config.decimals))
+                    .collect()
+            })
+            .collect();
+
+        let table = Table::new(header, rows, config.table_theme());
+        println!(
+            "{}",
+            draw_table(&table, config.width, &Default::default(), false)
+        );
+    }
+
+    /// The rows as JSON objects keyed by column
+    pub fn to_json(&self) -> JsonValue {
+        let rows: Vec<_> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let object = self
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, cell)| (column.clone(), cell.to_json()))
+                    .collect();
+
+                JsonValue::Object(object)
+            })
+            .collect();
+
+        json!(rows)
+    }
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?.into_iter().peekable(),
+        };
+
+        parser.query()
+    }
+
+    /// Runs the query over every month kept in `storage`
+    pub fn run(&self, storage: &mut dyn Storage) -> Result<QueryResults> {
+        let mut operations = vec![];
+
+        for month in storage.list_months()? {
+            let data = storage.load_month(&month, Access::ReadOnly)?;
+            operations.extend(
+                data.operations
+                    .into_iter()
+                    .map(|operation| (month, operation)),
+            );
+        }
+
+        Ok(self.evaluate(&operations))
+    }
+
+    /// Runs the query over `operations`, each along with the month it's kept in
+    pub fn evaluate(&self, operations: &[(BookkeepingFile, Operation)]) -> QueryResults {
+        let mut matching: Vec<_> = operations
+            .iter()
+            .filter(|(month, operation)| {
+                self.kind
+                    .as_ref()
+                    .is_none_or(|kind| *kind == operation.kind)
+                    && self
+                        .conditions
+                        .iter()
+                        .all(|condition| condition.holds(month, operation))
+            })
+            .collect();
+
+        if self.group_by.is_none() && self.aggregates.is_empty() {
+            matching.sort_by_key(|(month, operation)| {
+                (
+                    month.year(),
+                    month.month(),
+                    operation.day,
+                    operation.kind.clone(),
+                )
+            });
+
+            return list(&matching);
+        }
+
+        // Groups are counted unless something else is asked for
+        let aggregates = if self.aggregates.is_empty() {
+            vec![Aggregate::Count]
+        } else {
+            self.aggregates.clone()
+        };
+
+        let mut groups: BTreeMap<Option<Key>, Vec<&Operation>> = BTreeMap::new();
+        match self.group_by {
+            Some(field) => {
+                for (month, operation) in matching {
+                    for key in field.keys(month, operation) {
+                        groups.entry(Some(key)).or_default().push(operation);
+                    }
+                }
+            }
+            // Still computed when nothing matches, e.g. to count zero operations
+            None => {
+                groups.insert(None, matching.into_iter().map(|(_, op)| op).collect());
+            }
+        }
+
+        let mut columns: Vec<_> = self
+            .group_by
+            .map(|field| field.name().to_owned())
+            .into_iter()
+            .collect();
+        columns.extend(aggregates.iter().map(|aggregate| aggregate.name()));
+
+        let rows = groups
+            .into_iter()
+            .map(|(key, operations)| {
+                key.map(Key::into_cell)
+                    .into_iter()
+                    .chain(
+                        aggregates
+                            .iter()
+                            .map(|aggregate| aggregate.compute(&operations)),
+                    )
+                    .collect()
+            })
+            .collect();
+
+        QueryResults { columns, rows }
+    }
+}
+
+/// The operations themselves, one per row
+fn list(operations: &[&(BookkeepingFile, Operation)]) -> QueryResults {
+    let columns = ["month", "day", "kind", "amount", "description", "category"]
+        .map(String::from)
+        .to_vec();
+
+    let rows = operations
+        .iter()
+        .map(|(month, operation)| {
+            vec![
+                Cell::Text(month.as_str().to_owned()),
+                Cell::Count(operation.day.into()),
+                Cell::Text(operation.kind.name_and_symbol().0.to_owned()),
+                Cell::Number(operation.amount.clone()),
+                Cell::Text(operation.description.clone()),
+                operation.category.clone().map_or(Cell::Empty, Cell::Text),
+            ]
+        })
+        .collect();
+
+    QueryResults { columns, rows }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    /// Text within double quotes, which is never taken for a keyword
+    Quoted(String),
+    Symbol(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Word(word) => format!("'{word}'"),
+            Self::Quoted(text) => format!("\"{text}\""),
+            Self::Symbol(symbol) => format!("'{symbol}'"),
+        }
+    }
+}
+
+/// Symbols of the language, with the longer ones first so that they're matched first
+const SYMBOLS: [&str; 9] = [">=", "<=", "!=", "!~", ">", "<", "=", "~", ","];
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut rest = text.trim_start();
+
+    while rest.is_empty().not() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let (text, after) = quoted
+                .split_once('"')
+                .ok_or_else(|| invalid("a quote is never closed"))?;
+            tokens.push(Token::Quoted(text.to_owned()));
+            rest = after;
+        } else if let Some(symbol) = SYMBOLS.into_iter().find(|symbol| rest.starts_with(symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            let end = rest
+                .find(|char: char| char.is_whitespace() || char == '"' || "<>=!~,".contains(char))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                // A delimiter that doesn't start any symbol, e.g. a lone `!`
+                let unexpected = rest.chars().next().unwrap_or_default();
+                return Err(invalid(format!("unexpected '{unexpected}'")));
+            }
+            tokens.push(Token::Word(rest[..end].to_owned()));
+            rest = &rest[end..];
+        }
+
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Peekable<IntoIter<Token>>,
+}
+
+impl Parser {
+    fn query(&mut self) -> Result<Query> {
+        let mut query = Query::default();
+
+        if self.keyword("take") {
+            query.kind = Some(OperationType::Withdraw);
+        } else if self.keyword("put") {
+            query.kind = Some(OperationType::Deposit);
+        }
+
+        if self.keyword("where") {
+            loop {
+                query.conditions.push(self.condition()?);
+                if self.keyword("and").not() {
+                    break;
+                }
+            }
+        }
+
+        if self.keyword("group") {
+            if self.keyword("by").not() {
+                return Err(self.expected("'by' after 'group'"));
+            }
+            query.group_by = Some(self.field()?);
+        }
+
+        while self.tokens.peek().is_some() {
+            query.aggregates.push(self.aggregate()?);
+            self.tokens.next_if_eq(&Token::Symbol(","));
+        }
+
+        Ok(query)
+    }
+
+    fn condition(&mut self) -> Result<Condition> {
+        let field = self.field()?;
+
+        let comparison = match self.tokens.next() {
+            Some(Token::Symbol(symbol)) => Comparison::ALL
+                .into_iter()
+                .find(|comparison| comparison.symbol() == symbol),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            invalid(format!(
+                "expected one of =, !=, >, >=, <, <=, ~ or !~ after '{}'",
+                field.name()
+            ))
+        })?;
+
+        let text = match self.tokens.next() {
+            Some(Token::Word(text) | Token::Quoted(text)) => text,
+            _ => {
+                return Err(invalid(format!(
+                    "expected a value to compare '{}' to",
+                    field.name()
+                )))
+            }
+        };
+
+        let is_ordering =
+            matches!(comparison, Comparison::Contains | Comparison::NotContains).not();
+        let value = match field {
+            Field::Month if is_ordering => {
+                parse_month(&text).map(|(year, month)| Value::Month(year, month))
+            }
+            Field::Day | Field::Amount if is_ordering => text.parse().ok().map(Value::Number),
+            Field::Kind if matches!(comparison, Comparison::Equal | Comparison::NotEqual) => {
+                match text.to_lowercase().as_str() {
+                    "take" => Some(Value::Kind(OperationType::Withdraw)),
+                    "put" => Some(Value::Kind(OperationType::Deposit)),
+                    _ => None,
+                }
+            }
+            Field::Description | Field::Category | Field::Tag
+                if matches!(comparison, Comparison::Equal | Comparison::NotEqual).not()
+                    && is_ordering =>
+            {
+                return Err(invalid(format!(
+                    "'{}' is text, which is only compared with =, !=, ~ or !~",
+                    field.name()
+                )));
+            }
+            Field::Description | Field::Category | Field::Tag => {
+                let text = text.to_lowercase();
+                let text = match field {
+                    Field::Tag => text.strip_prefix('#').map(str::to_owned).unwrap_or(text),
+                    _ => text,
+                };
+                Some(Value::Text(text))
+            }
+            _ => {
+                return Err(invalid(format!(
+                    "'{}' can't be compared with {}",
+                    field.name(),
+                    comparison.symbol()
+                )))
+            }
+        }
+        .ok_or_else(|| invalid(format!("'{text}' is not a valid {}", field.name())))?;
+
+        Ok(Condition { field, comparison, value })
+    }
+
+    fn aggregate(&mut self) -> Result<Aggregate> {
+        let function = match self.tokens.next() {
+            Some(Token::Word(word)) => word.to_lowercase(),
+            token => {
+                return Err(invalid(format!(
+                    "expected count, sum, avg, min or max, found {}",
+                    token.map_or("nothing".into(), |token| token.describe())
+                )))
+            }
+        };
+
+        let aggregate: fn(Field) -> Aggregate = match function.as_str() {
+            "count" => return Ok(Aggregate::Count),
+            "sum" => Aggregate::Sum,
+            "avg" => Aggregate::Average,
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            _ => {
+                return Err(invalid(format!(
+                    "expected count, sum, avg, min or max, found '{function}'"
+                )))
+            }
+        };
+
+        let field = self.field()?;
+        if field.is_numeric().not() {
+            return Err(invalid(format!(
+                "only amount and day can be computed with {function}, not '{}'",
+                field.name()
+            )));
+        }
+
+        Ok(aggregate(field))
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        let field = match self.tokens.peek() {
+            Some(Token::Word(word)) => Field::from_name(word),
+            _ => None,
+        };
+
+        match field {
+            Some(field) => {
+                self.tokens.next();
+                Ok(field)
+            }
+            None => Err(self.expected(
+                "a field, one of month, day, kind, amount, description, category or tag",
+            )),
+        }
+    }
+
+    /// Whether the next token is the keyword `keyword`, consuming it if so
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(
+                |token| matches!(token, Token::Word(word) if word.eq_ignore_ascii_case(keyword)),
+            )
+            .is_some()
+    }
+
+    fn expected(&mut self, what: &str) -> Error {
+        let found = self.tokens.peek().map_or("nothing".into(), Token::describe);
+
+        invalid(format!("expected {what}, found {found}"))
+    }
+}
+
+/// The year and month of `text`, written as `YYYY-MM` or `MM-YYYY`
+fn parse_month(text: &str) -> Option<(i32, u32)> {
+    let month = BookkeepingFile::from_file_name(text).or_else(|| {
+        let (year, month) = text.split_once('-')?;
+        (year.len() == 4).then_some(())?;
+        BookkeepingFile::from_year_and_month(year.parse().ok()?, month.parse().ok()?)
+    })?;
+
+    Some((month.year(), month.month()))
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidQuery(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use super::{average, Cell, Query};
+    use crate::{
+        file::BookkeepingFile,
+        parser::{Operation, OperationType},
+    };
+
+    fn operations() -> Vec<(BookkeepingFile, Operation)> {
+        [
+            (
+                "12-2025",
+                5,
+                OperationType::Withdraw,
+                40,
+                "Market",
+                Some("food"),
+            ),
+            (
+                "01-2026",
+                3,
+                OperationType::Withdraw,
+                12,
+                "Lunch #work",
+                Some("food"),
+            ),
+            ("01-2026", 5, OperationType::Deposit, 3000, "Salary", None),
+            (
+                "01-2026",
+                20,
+                OperationType::Withdraw,
+                30,
+                "Uber #work",
+                Some("transport"),
+            ),
+            (
+                "02-2026",
+                8,
+                OperationType::Withdraw,
+                25,
+                "Dinner",
+                Some("food"),
+            ),
+            (
+                "02-2026",
+                9,
+                OperationType::Withdraw,
+                9,
+                "Bus",
+                Some("transport"),
+            ),
+        ]
+        .into_iter()
+        .map(|(month, day, kind, amount, description, category)| {
+            let mut operation = Operation::new(day, kind, BigDecimal::from(amount), description);
+            operation.category = category.map(String::from);
+
+            (BookkeepingFile::from_file_name(month).unwrap(), operation)
+        })
+        .collect()
+    }
+
+    fn run(query: &str) -> Vec<Vec<Cell>> {
+        Query::parse(query).unwrap().evaluate(&operations()).rows
+    }
+
+    fn text(text: &str) -> Cell {
+        Cell::Text(text.into())
+    }
+
+    fn number(number: u32) -> Cell {
+        Cell::Number(BigDecimal::from(number))
+    }
+
+    #[test]
+    fn rounds_averages_that_never_end() {
+        assert_eq!(average(BigDecimal::from(1), 3).to_string(), "0.3333");
+        assert_eq!(average(BigDecimal::from(2), 3).to_string(), "0.6667");
+        assert_eq!(average(BigDecimal::from(10), 4).to_string(), "2.5");
+    }
+
+    #[test]
+    fn groups_and_sums() {
+        let results = Query::parse(
+            "take where category = food and month >= 2026-01 group by month sum amount",
+        )
+        .unwrap()
+        .evaluate(&operations());
+
+        assert_eq!(results.columns, ["month", "sum amount"]);
+        assert_eq!(
+            results.rows,
+            [
+                vec![text("01-2026"), number(12)],
+                vec![text("02-2026"), number(25)]
+            ]
+        );
+        assert_eq!(
+            results.to_json()[1],
+            serde_json::json!({ "month": "02-2026", "sum amount": "25" })
+        );
+
+        assert_eq!(
+            run("take group by tag count, max amount"),
+            [vec![text("work"), Cell::Count(2), number(30)]]
+        );
+        assert_eq!(
+            run("where kind != put and description !~ \"er\" avg amount"),
+            [vec![Cell::Number("20.3333".parse().unwrap())]]
+        );
+        assert_eq!(
+            run("where amount>1000 and day<5 count"),
+            [vec![Cell::Count(0)]]
+        );
+    }
+
+    #[test]
+    fn lists_the_matches_without_aggregates() {
+        let rows = run("where category ~ TRANS and month < 02-2026");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0][4], text("Uber #work"));
+
+        assert_eq!(run("put").len(), 1);
+        assert_eq!(run("").len(), 6);
+    }
+
+    #[test]
+    fn explains_what_is_wrong() {
+        let error = |query| Query::parse(query).unwrap_err().to_string();
+
+        assert_eq!(
+            error("take where colour = red"),
+            "Invalid query: expected a field, one of month, day, kind, amount, description, \
+             category or tag, found 'colour'"
+        );
+        assert_eq!(
+            error("where amount ~ 10"),
+            "Invalid query: 'amount' can't be compared with ~"
+        );
+        assert_eq!(
+            error("where month > october"),
+            "Invalid query: 'october' is not a valid month"
+        );
+        assert_eq!(
+            error("group by category sum description"),
+            "Invalid query: only amount and day can be computed with sum, not 'description'"
+        );
+        assert_eq!(
+            error("where description = \"Uber"),
+            "Invalid query: a quote is never closed"
+        );
+        assert_eq!(
+            error("where description = wow!"),
+            "Invalid query: unexpected '!'"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use std::{collections::HashMap, ops::Not};
+
+use bigdecimal::{BigDecimal, Zero};
+use nu_table::{draw_table, StyledString, Table, TextStyle};
+use serde_json::{json, Value as JsonValue};
+
+use crate::{
+    bookkeeper::{status::table_header_from_column_names, BookkeeperStatus},
+    config::Config,
+    file::BookkeepingFile,
+    query::average,
+    storage::Storage,
+    Access, Result,
+};
+
+/// The totals of a month of the books
+#[derive(Debug, Clone)]
+pub struct MonthSummary {
+    pub month: BookkeepingFile,
+    pub income: BigDecimal,
+    pub expenses: BigDecimal,
+    /// What was meant to be spent, at most
+    pub target: Option<i64>,
+}
+
+impl MonthSummary {
+    pub fn net(&self) -> BigDecimal {
+        &self.income - &self.expenses
+    }
+}
+
+/// How every month of a year went
+#[derive(Debug)]
+pub struct YearReport {
+    pub year: i32,
+    /// The months in the books, in order
+    pub months: Vec<MonthSummary>,
+}
+
+impl YearReport {
+    /// Sums up the months of `year` kept in `storage`
+    pub fn load(storage: &mut dyn Storage, year: i32) -> Result<Self> {
+        let mut months = vec![];
+
+        for month in storage.list_months()? {
+            if month.year() != year {
+                continue;
+            }
+
+            let data = storage.load_month(&month, Access::ReadOnly)?;
+            let status = BookkeeperStatus::from_operations(data.operations);
+
+            months.push(MonthSummary {
+                month,
+                income: status.put_total,
+                expenses: status.take_total,
+                target: data.target,
+            });
+        }
+        months.sort_by_key(|summary| summary.month.month());
+
+        Ok(Self { year, months })
+    }
+
+    /// The balance at the end of each month, counting from the start of the year
+    pub fn balances(&self) -> Vec<BigDecimal> {
+        let mut balance = BigDecimal::zero();
+
+        self.months
+            .iter()
+            .map(|summary| {
+                balance += summary.net();
+                balance.clone()
+            })
+            .collect()
+    }
+
+    pub fn income(&self) -> BigDecimal {
+        self.months.iter().map(|summary| &summary.income).sum()
+    }
+
+    pub fn expenses(&self) -> BigDecimal {
+        self.months.iter().map(|summary| &summary.expenses).sum()
+    }
+
+    /// The targets of the months that have one, along with what was spent in them
+    fn targeted(&self) -> (BigDecimal, BigDecimal) {
+        self.months
+            .iter()
+            .filter_map(|summary| Some((BigDecimal::from(summary.target?), &summary.expenses)))
+            .fold(
+                Default::default(),
+                |(targets, spent), (target, expenses)| (targets + target, spent + expenses),
+            )
+    }
+
+    pub fn display(&self, config: &Config) {
+        let Config { currency, decimals, .. } = config;
+        let money = |amount: &BigDecimal| format!("{currency} {amount:.decimals$}");
+        let left = |text: String| StyledString::new(text, TextStyle::basic_left());
+        let right = |text: String| StyledString::new(text, TextStyle::basic_right());
+
+        let header = table_header_from_column_names(&[
+            "month", "income", "expenses", "net", "balance", "target", "spent",
+        ]);
+
+        let rows = self
+            .months
+            .iter()
+            .zip(self.balances())
+            .map(|(summary, balance)| {
+                let target = summary.target.map(BigDecimal::from);
+
+                vec![
+                    left(summary.month.as_str().to_owned()),
+                    right(money(&summary.income)),
+                    right(money(&summary.expenses)),
+                    right(money(&summary.net())),
+                    right(money(&balance)),
+                    right(target.as_ref().map(money).unwrap_or_default()),
+                    right(
+                        target
+                            .map(|target| share(&summary.expenses, &target))
+                            .unwrap_or_default(),
+                    ),
+                ]
+            })
+            .collect();
+
+        let count = self.months.len().max(1);
+        let (income, expenses) = (self.income(), self.expenses());
+        let net = &income - &expenses;
+        let (targets, spent) = self.targeted();
+        let targeted_months = self
+            .months
+            .iter()
+            .filter(|summary| summary.target.is_some())
+            .count();
+
+        let totals = vec![
+            left("total".into()),
+            right(money(&income)),
+            right(money(&expenses)),
+            right(money(&net)),
+            right(money(&net)),
+            right(money(&targets)),
+            right(share(&spent, &targets)),
+        ];
+        let averages = vec![
+            left("average".into()),
+            right(money(&average(income, count))),
+            right(money(&average(expenses, count))),
+            right(money(&average(net, count))),
+            right(String::new()),
+            right(if targeted_months > 0 {
+                money(&average(targets, targeted_months))
+            } else {
+                String::new()
+            }),
+            right(String::new()),
+        ];
+
+        let table =
+            Table::new(header, rows, config.table_theme()).with_footer(vec![totals, averages]);
+        println!(
+            "{}",
+            draw_table(&table, config.width, &HashMap::new(), true)
+        );
+    }
+
+    /// The report as JSON, with amounts as strings so that no precision is lost
+    pub fn to_json(&self) -> JsonValue {
+        let months: Vec<_> = self
+            .months
+            .iter()
+            .zip(self.balances())
+            .map(|(summary, balance)| {
+                json!({
+                    "month": summary.month.as_str(),
+                    "income": summary.income.to_string(),
+                    "expenses": summary.expenses.to_string(),
+                    "net": summary.net().to_string(),
+                    "balance": balance.to_string(),
+                    "target": summary.target,
+                })
+            })
+            .collect();
+
+        let count = self.months.len().max(1);
+        let (income, expenses) = (self.income(), self.expenses());
+
+        json!({
+            "year": self.year,
+            "months": months,
+            "income": income.to_string(),
+            "expenses": expenses.to_string(),
+            "net": (&income - &expenses).to_string(),
+            "average_income": average(income.clone(), count).to_string(),
+            "average_expenses": average(expenses.clone(), count).to_string(),
+        })
+    }
+}
+
+/// How much of `target` was `spent`, as a percentage, or nothing without a target
+fn share(spent: &BigDecimal, target: &BigDecimal) -> String {
+    if target.is_zero().not() {
+        format!("{:.0}%", (spent * BigDecimal::from(100) / target).round(0))
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use super::{share, YearReport};
+    use crate::{
+        file::BookkeepingFile,
+        parser::OperationType::{Deposit, Withdraw},
+        storage::{MemoryStorage, Storage},
+    };
+
+    #[test]
+    fn sums_up_the_months_of_the_year() {
+        let mut storage = MemoryStorage::with_operations(&[
+            ("12-2025", 1, Deposit, 500, "Salary"),
+            ("12-2025", 2, Withdraw, 100, "Rent"),
+            ("02-2026", 1, Deposit, 3000, "Salary"),
+            ("02-2026", 2, Withdraw, 2500, "Rent"),
+            ("01-2026", 1, Deposit, 3000, "Salary"),
+            ("01-2026", 2, Withdraw, 1000, "Rent"),
+            ("03-2026", 1, Deposit, 0, "Salary"),
+            ("03-2026", 2, Withdraw, 450, "Rent"),
+        ]);
+        for (month, target) in [("02-2026", 2000), ("03-2026", 500)] {
+            let month = BookkeepingFile::from_file_name(month).unwrap();
+            storage.set_target(&month, Some(target)).unwrap();
+        }
+
+        let report = YearReport::load(&mut storage, 2026).unwrap();
+
+        let months: Vec<_> = report
+            .months
+            .iter()
+            .map(|summary| summary.month.as_str())
+            .collect();
+        assert_eq!(months, ["01-2026", "02-2026", "03-2026"]);
+        assert_eq!(report.balances(), [2000, 2500, 2050].map(BigDecimal::from));
+        assert_eq!(
+            report.targeted(),
+            (BigDecimal::from(2500), BigDecimal::from(2950))
+        );
+
+        let json = report.to_json();
+        assert_eq!(json["net"], "2050");
+        assert_eq!(json["average_expenses"], "1316.6667");
+        assert_eq!(json["months"][1]["target"], 2000);
+    }
+
+    #[test]
+    fn rounds_shares_of_the_target() {
+        let share_of = |spent, target| share(&BigDecimal::from(spent), &BigDecimal::from(target));
+
+        assert_eq!(share_of(1, 3), "33%");
+        assert_eq!(share_of(2, 3), "67%");
+        assert_eq!(share_of(450, 500), "90%");
+        assert_eq!(share_of(450, 0), "");
+    }
+}
@@ -0,0 +1,430 @@
+use std::{
+    collections::HashMap,
+    ops::{Bound, Not},
+    str::FromStr,
+};
+
+use bigdecimal::BigDecimal;
+use nu_table::{draw_table, StyledString, Table, TextStyle};
+use regex::{Regex, RegexBuilder};
+use serde_json::json;
+
+use crate::{
+    bookkeeper::{
+        status::{operation_json, table_header_from_column_names, table_row_from_operation},
+        BookkeeperStatus, Sorting,
+    },
+    config::Config,
+    file::BookkeepingFile,
+    parser::{Operation, OperationType},
+    storage::Storage,
+    Access, Result,
+};
+
+/// What the description of an operation must match
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Text contained in the description, ignoring case
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    /// Matches descriptions containing `text`, or matching it as a regular expression
+    /// if `is_regex` is set, ignoring case either way
+    pub fn new(text: &str, is_regex: bool) -> Result<Self> {
+        if is_regex {
+            let regex = RegexBuilder::new(text).case_insensitive(true).build()?;
+            Ok(Self::Regex(regex))
+        } else {
+            Ok(Self::Substring(text.to_lowercase()))
+        }
+    }
+
+    pub fn matches(&self, description: &str) -> bool {
+        match self {
+            Self::Substring(text) => description.to_lowercase().contains(text),
+            Self::Regex(regex) => regex.is_match(description),
+        }
+    }
+}
+
+/// Amounts between two bounds, e.g. `>100`, `<=50`, `10..20`, `10..`, `..20` or just `42`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountRange {
+    min: Bound<BigDecimal>,
+    max: Bound<BigDecimal>,
+}
+
+impl AmountRange {
+    /// The range written as `text`, if it's in one of the formats above
+    ///
+    /// `a..b` includes both ends, either of which can be left out, and `a` can't be
+    /// larger than `b`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let amount = |text: &str| BigDecimal::from_str(text.trim()).ok();
+        let end = |text: &str| match text.trim() {
+            "" => Some(Bound::Unbounded),
+            text => Some(Bound::Included(amount(text)?)),
+        };
+        let text = text.trim();
+
+        let (min, max) = if let Some(min) = text.strip_prefix(">=") {
+            (Bound::Included(amount(min)?), Bound::Unbounded)
+        } else if let Some(min) = text.strip_prefix('>') {
+            (Bound::Excluded(amount(min)?), Bound::Unbounded)
+        } else if let Some(max) = text.strip_prefix("<=") {
+            (Bound::Unbounded, Bound::Included(amount(max)?))
+        } else if let Some(max) = text.strip_prefix('<') {
+            (Bound::Unbounded, Bound::Excluded(amount(max)?))
+        } else if let Some((min, max)) = text.split_once("..") {
+            (end(min)?, end(max)?)
+        } else {
+            let exactly = amount(text.strip_prefix('=').unwrap_or(text))?;
+            (Bound::Included(exactly.clone()), Bound::Included(exactly))
+        };
+
+        let is_valid = match (&min, &max) {
+            (Bound::Unbounded, Bound::Unbounded) => false,
+            (Bound::Included(min), Bound::Included(max)) => min <= max,
+            _ => true,
+        };
+
+        is_valid.then_some(Self { min, max })
+    }
+
+    pub fn contains(&self, amount: &BigDecimal) -> bool {
+        let above_min = match &self.min {
+            Bound::Included(min) => amount >= min,
+            Bound::Excluded(min) => amount > min,
+            Bound::Unbounded => true,
+        };
+        let below_max = match &self.max {
+            Bound::Included(max) => amount <= max,
+            Bound::Excluded(max) => amount < max,
+            Bound::Unbounded => true,
+        };
+
+        above_min && below_max
+    }
+}
+
+/// Days of a month from `first` to `last`, e.g. `1..15`, `20..`, `..10` or just `5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayRange {
+    first: u8,
+    last: u8,
+}
+
+impl DayRange {
+    /// The range written as `text`, if it's in one of the formats above
+    pub fn parse(text: &str) -> Option<Self> {
+        let day = |text: &str| {
+            text.trim()
+                .parse()
+                .ok()
+                .filter(|day| (1..=31).contains(day))
+        };
+
+        let (first, last) = match text.split_once("..") {
+            Some(("", "")) => return None,
+            Some((first, "")) => (day(first)?, 31),
+            Some(("", last)) => (1, day(last)?),
+            Some((first, last)) => (day(first)?, day(last)?),
+            None => (day(text)?, day(text)?),
+        };
+
+        (first <= last).then_some(Self { first, last })
+    }
+
+    pub fn contains(&self, day: u8) -> bool {
+        (self.first..=self.last).contains(&day)
+    }
+}
+
+/// The tags in `description`, which are the words starting with `#`, without it
+///
+/// E.g. `Dinner #work #travel` is tagged `work` and `travel`.
+pub fn tags(description: &str) -> impl Iterator<Item = &str> {
+    description
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('#'))
+        .map(|tag| tag.trim_end_matches(|char: char| char.is_ascii_punctuation()))
+        .filter(|tag| tag.is_empty().not())
+}
+
+/// What an operation must be like to be shown, where criteria that aren't set match anything
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub description: Option<Pattern>,
+    pub amount: Option<AmountRange>,
+    pub kind: Option<OperationType>,
+    pub days: Option<DayRange>,
+    /// Compared ignoring case
+    pub category: Option<String>,
+    /// Compared ignoring case, with or without the `#`
+    pub tag: Option<String>,
+}
+
+impl Filter {
+    /// Whether no criteria are set, so that every operation matches
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none()
+            && self.amount.is_none()
+            && self.kind.is_none()
+            && self.days.is_none()
+            && self.category.is_none()
+            && self.tag.is_none()
+    }
+
+    pub fn matches(&self, operation: &Operation) -> bool {
+        let tag = self
+            .tag
+            .as_deref()
+            .map(|tag| tag.strip_prefix('#').unwrap_or(tag));
+
+        self.description
+            .as_ref()
+            .is_none_or(|pattern| pattern.matches(&operation.description))
+            && self
+                .amount
+                .as_ref()
+                .is_none_or(|range| range.contains(&operation.amount))
+            && self
+                .kind
+                .as_ref()
+                .is_none_or(|kind| *kind == operation.kind)
+            && self.days.is_none_or(|days| days.contains(operation.day))
+            && self.category.as_deref().is_none_or(|category| {
+                operation
+                    .category
+                    .as_deref()
+                    .is_some_and(|other| other.eq_ignore_ascii_case(category))
+            })
+            && tag.is_none_or(|tag| {
+                tags(&operation.description).any(|other| other.eq_ignore_ascii_case(tag))
+            })
+    }
+}
+
+/// The operations that matched a search, along with their months
+pub struct SearchResults {
+    /// Sorted by month, then day
+    pub matches: Vec<(BookkeepingFile, Operation)>,
+    /// The totals of the matches
+    pub status: BookkeeperStatus,
+}
+
+impl SearchResults {
+    /// Looks for the operations matching `filter` in the months kept in `storage`,
+    /// from `from` to `to` if given
+    pub fn search(
+        storage: &mut dyn Storage,
+        filter: &Filter,
+        from: Option<BookkeepingFile>,
+        to: Option<BookkeepingFile>,
+    ) -> Result<Self> {
+        let key = |month: &BookkeepingFile| (month.year(), month.month());
+        let is_in_range = |month: &BookkeepingFile| {
+            from.is_none_or(|from| key(month) >= key(&from))
+                && to.is_none_or(|to| key(month) <= key(&to))
+        };
+
+        let mut months = storage.list_months()?;
+        months.retain(is_in_range);
+        months.sort_by_key(key);
+
+        let mut matches = vec![];
+        for month in months {
+            let mut operations: Vec<_> = storage
+                .load_month(&month, Access::ReadOnly)?
+                .operations
+                .into_iter()
+                .filter(|operation| filter.matches(operation))
+                .collect();
+            operations.sort_by(|a, b| a.day.cmp(&b.day).then(a.kind.cmp(&b.kind)));
+
+            matches.extend(operations.into_iter().map(|operation| (month, operation)));
+        }
+
+        let status =
+            BookkeeperStatus::from_operations(matches.iter().map(|(_, op)| op.clone()).collect());
+
+        Ok(Self { matches, status })
+    }
+
+    /// Sorts the matches, which are sorted by month and day otherwise
+    ///
+    /// Days of different months are compared as they are, so this is meant for
+    /// the matches of a single month.
+    pub fn sort(&mut self, sorting: &Sorting) {
+        sorting.apply(&mut self.matches, |(_, operation)| operation);
+    }
+
+    pub fn display(&self, config: &Config) {
+        self.status.display_value_table(config);
+
+        let header = table_header_from_column_names(&[
+            "month",
+            "day",
+            "op",
+            "amount",
+            "description",
+            "category",
+        ]);
+        let rows = self
+            .matches
+            .iter()
+            .map(|(month, operation)| {
+                let mut row = vec![StyledString::new(
+                    month.as_str().to_owned(),
+                    TextStyle::basic_left(),
+                )];
+                row.extend(table_row_from_operation(operation, config.decimals));
+                row.push(StyledString::new(
+                    operation.category.clone().unwrap_or_default(),
+                    TextStyle::basic_left(),
+                ));
+
+                row
+            })
+            .collect();
+
+        let table = Table::new(header, rows, config.table_theme());
+        println!(
+            "{}",
+            draw_table(&table, config.width, &HashMap::new(), false)
+        );
+    }
+
+    /// The matches as JSON, with amounts as strings so that no precision is lost
+    pub fn to_json(&self) -> serde_json::Value {
+        let BookkeeperStatus { put_total, take_total, .. } = &self.status;
+
+        let operations: Vec<_> = self
+            .matches
+            .iter()
+            .map(|(month, operation)| {
+                let mut json = operation_json(operation);
+                json["month"] = month.as_str().into();
+                json
+            })
+            .collect();
+
+        json!({
+            "incoming": put_total.to_string(),
+            "outgoing": take_total.to_string(),
+            "balance": (put_total - take_total).to_string(),
+            "operations": operations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Not;
+
+    use bigdecimal::BigDecimal;
+
+    use super::{AmountRange, DayRange, Filter, Pattern, SearchResults};
+    use crate::{
+        file::BookkeepingFile,
+        parser::{Operation, OperationType},
+        storage::{MemoryStorage, Storage},
+    };
+
+    fn operation(day: u8, kind: OperationType, amount: u32, description: &str) -> Operation {
+        Operation::new(day, kind, BigDecimal::from(amount), description)
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let amounts = |text| AmountRange::parse(text).unwrap();
+        assert!(amounts(">100").contains(&BigDecimal::from(101)));
+        assert!(amounts(">100").contains(&BigDecimal::from(100)).not());
+        assert!(amounts(">=100").contains(&BigDecimal::from(100)));
+        assert!(amounts("<50.5").contains(&"50.4".parse().unwrap()));
+        assert!(amounts("10..20").contains(&BigDecimal::from(20)));
+        assert!(amounts("42").contains(&BigDecimal::from(42)));
+        assert!(amounts("10..").contains(&BigDecimal::from(1000)));
+        assert!(amounts("..20").contains(&BigDecimal::from(21)).not());
+        assert_eq!(AmountRange::parse("lots"), None);
+        assert_eq!(AmountRange::parse("20..10"), None);
+        assert_eq!(AmountRange::parse(".."), None);
+
+        let days = |text| DayRange::parse(text).unwrap();
+        assert!(days("1..15").contains(15));
+        assert!(days("20..").contains(31));
+        assert!(days("..10").contains(11).not());
+        assert!(days("5").contains(5));
+        assert_eq!(DayRange::parse("15..1"), None);
+        assert_eq!(DayRange::parse("32"), None);
+    }
+
+    #[test]
+    fn matches_every_criterion_set() {
+        let mut dinner = operation(12, OperationType::Withdraw, 120, "Dinner #work");
+        dinner.category = Some("Food".into());
+
+        let by_regex = Filter {
+            description: Some(Pattern::new("^din+er", true).unwrap()),
+            ..Filter::default()
+        };
+        assert!(by_regex.matches(&dinner));
+
+        let by_everything = Filter {
+            description: Some(Pattern::new("NER", false).unwrap()),
+            amount: AmountRange::parse(">100"),
+            kind: Some(OperationType::Withdraw),
+            days: DayRange::parse("10..15"),
+            category: Some("food".into()),
+            tag: Some("#Work".into()),
+        };
+        assert!(by_everything.matches(&dinner));
+
+        let by_other_tag = Filter {
+            tag: Some("travel".into()),
+            ..Filter::default()
+        };
+        assert!(by_other_tag.matches(&dinner).not());
+        assert!(Pattern::new("(", true).is_err());
+    }
+
+    #[test]
+    fn searches_a_range_of_months() {
+        let mut storage = MemoryStorage::new();
+        for (month, operation) in [
+            ("08-2026", operation(3, OperationType::Withdraw, 30, "Uber")),
+            ("09-2026", operation(9, OperationType::Withdraw, 25, "Uber")),
+            (
+                "09-2026",
+                operation(2, OperationType::Deposit, 10, "Uber refund"),
+            ),
+            ("10-2026", operation(1, OperationType::Withdraw, 40, "Uber")),
+            (
+                "10-2026",
+                operation(5, OperationType::Withdraw, 900, "Rent"),
+            ),
+        ] {
+            let month = BookkeepingFile::from_file_name(month).unwrap();
+            storage.append_operation(&month, &operation).unwrap();
+        }
+
+        let filter = Filter {
+            description: Some(Pattern::new("uber", false).unwrap()),
+            ..Filter::default()
+        };
+        let from = BookkeepingFile::from_file_name("09-2026");
+        let results = SearchResults::search(&mut storage, &filter, from, None).unwrap();
+
+        let found: Vec<_> = results
+            .matches
+            .iter()
+            .map(|(month, operation)| (month.as_str(), operation.day))
+            .collect();
+        assert_eq!(found, [("09-2026", 2), ("09-2026", 9), ("10-2026", 1)]);
+        assert_eq!(results.status.take_total, BigDecimal::from(65));
+        assert_eq!(results.status.put_total, BigDecimal::from(10));
+        assert_eq!(results.to_json()["balance"], "-55");
+    }
+}
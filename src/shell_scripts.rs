@@ -0,0 +1,236 @@
+use std::io::{self, Write};
+
+use clap::Command;
+use clap_complete::Shell;
+
+const BIN_NAME: &str = "porquinho";
+
+/// Runs `porquinho complete` for the candidates clap can't know about, falling
+/// back to what clap generated for everything else
+const BASH_FROM_BOOKS: &str = r#"
+# Whether the word being completed is the description of `take` or `put`, which
+# comes right after the amount, wherever options are given
+_porquinho_completing_description() {
+    local subcommand="" positionals=0 index=1
+
+    while [[ ${index} -lt ${COMP_CWORD} ]]; do
+        case "${COMP_WORDS[index]}" in
+            -c|--category|--data-dir|--profile)
+                # bash splits `--profile=work` into three words
+                (( index++ ))
+                if [[ "${COMP_WORDS[index]}" == "=" ]]; then
+                    (( index++ ))
+                fi
+                ;;
+            -*)
+                ;;
+            *)
+                if [[ -z "${subcommand}" ]]; then
+                    subcommand="${COMP_WORDS[index]}"
+                else
+                    (( positionals++ ))
+                fi
+                ;;
+        esac
+        (( index++ ))
+    done
+
+    [[ ${subcommand} =~ ^(take|put)$ && ${positionals} -eq 1 ]]
+}
+
+_porquinho_from_books() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local kind=""
+
+    if [[ "${prev}" == "=" && ${COMP_CWORD} -gt 1 ]]; then
+        prev="${COMP_WORDS[COMP_CWORD-2]}"
+    fi
+
+    case "${prev}" in
+        -c|--category)
+            kind="categories"
+            ;;
+        --profile)
+            kind="profiles"
+            ;;
+        *)
+            if _porquinho_completing_description; then
+                kind="descriptions"
+            fi
+            ;;
+    esac
+
+    if [[ -z "${kind}" ]]; then
+        _porquinho "$@"
+        return
+    fi
+
+    if [[ "${cur}" == "=" ]]; then
+        cur=""
+    fi
+
+    local IFS=$'\n'
+    COMPREPLY=( $(compgen -W "$(porquinho complete "${kind}" 2>/dev/null)" -- "${cur}") )
+    COMPREPLY=( "${COMPREPLY[@]// /\\ }" )
+}
+
+complete -F _porquinho_from_books -o bashdefault -o default porquinho
+"#;
+
+const ZSH_FROM_BOOKS: &str = r#"(( $+functions[_porquinho_complete] )) ||
+_porquinho_complete() {
+    local -a candidates
+    candidates=("${(@f)$(porquinho complete "$1" 2>/dev/null)}")
+    compadd -a candidates
+}
+
+"#;
+
+const FISH_FROM_BOOKS: &str = r#"
+# Whether the token being completed is the description of `take` or `put`, which
+# comes right after the amount, wherever options are given
+function __porquinho_completing_description
+    set -l subcommand
+    set -l positionals 0
+    set -l tokens (commandline -opc)[2..-1]
+
+    while set -q tokens[1]
+        switch $tokens[1]
+            case -c --category --data-dir --profile
+                set -e tokens[1]
+            case '-*'
+            case '*'
+                if test -z "$subcommand"
+                    set subcommand $tokens[1]
+                else
+                    set positionals (math $positionals + 1)
+                end
+        end
+        set -e tokens[1]
+    end
+
+    contains -- "$subcommand" take put; and test $positionals -eq 1
+end
+
+complete -c porquinho -n "__fish_seen_subcommand_from take put" -s c -l category -x -a "(porquinho complete categories 2>/dev/null)"
+complete -c porquinho -l profile -x -a "(porquinho complete profiles 2>/dev/null)"
+complete -c porquinho -n __porquinho_completing_description -f -a "(porquinho complete descriptions 2>/dev/null)"
+"#;
+
+/// Prints the completion script of `shell`, which also completes descriptions,
+/// categories and profiles from the books and settings, except in PowerShell
+/// and Elvish
+pub fn print_script(shell: Shell, command: Command) -> io::Result<()> {
+    io::stdout()
+        .lock()
+        .write_all(script(shell, command).as_bytes())
+}
+
+fn script(shell: Shell, mut command: Command) -> String {
+    let mut generated = Vec::new();
+    clap_complete::generate(shell, &mut command, BIN_NAME, &mut generated);
+    // Safety: clap only generates UTF-8 out of the UTF-8 of the command
+    let mut script = String::from_utf8(generated).unwrap();
+
+    match shell {
+        Shell::Bash => script.push_str(BASH_FROM_BOOKS),
+        Shell::Fish => script.push_str(FISH_FROM_BOOKS),
+        Shell::Zsh => {
+            // The values of these arguments are left for zsh to complete otherwise
+            for (empty, from_books) in [
+                (":CATEGORY: '", ":CATEGORY:_porquinho_complete categories'"),
+                (":PROFILE: '", ":PROFILE:_porquinho_complete profiles'"),
+                (
+                    ":description:'",
+                    ":description:_porquinho_complete descriptions'",
+                ),
+            ] {
+                script = script.replace(empty, from_books);
+            }
+
+            let entry_point = format!("_{BIN_NAME} \"$@\"");
+            if let Some(start) = script.rfind(&entry_point) {
+                script.insert_str(start, ZSH_FROM_BOOKS);
+            }
+        }
+        _ => {}
+    }
+
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use clap::CommandFactory;
+    use clap_complete::Shell;
+
+    use super::script;
+    use crate::cli::Opts;
+
+    #[test]
+    fn completes_from_the_books() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = script(shell, Opts::command());
+
+            assert!(script.contains("porquinho complete"), "{shell}");
+            for kind in ["descriptions", "categories", "profiles"] {
+                assert!(script.contains(kind), "{shell} doesn't complete {kind}");
+            }
+        }
+
+        let zsh = script(Shell::Zsh, Opts::command());
+        assert!(zsh.contains(":description:_porquinho_complete descriptions'"));
+        assert!(zsh.trim_end().ends_with("_porquinho \"$@\""));
+    }
+
+    /// What bash completes the last of `words` to, with `porquinho complete` answering `Lunch`
+    fn bash_completions(words: &[&str]) -> String {
+        let script = script(Shell::Bash, Opts::command());
+        let words: Vec<_> = words.iter().map(|word| format!("'{word}'")).collect();
+        let completing = format!(
+            "porquinho() {{ echo Lunch; }}\n\
+             _porquinho() {{ COMPREPLY=(clap); }}\n\
+             COMP_WORDS=({})\n\
+             COMP_CWORD=$(( ${{#COMP_WORDS[@]}} - 1 ))\n\
+             _porquinho_from_books\n\
+             echo \"${{COMPREPLY[@]}}\"",
+            words.join(" ")
+        );
+
+        let output = Command::new("bash")
+            .arg("-c")
+            .arg(format!("{script}\n{completing}"))
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_owned()
+    }
+
+    #[test]
+    fn completes_descriptions_after_options_in_bash() {
+        let cases = [
+            (&["porquinho", "take", "12", ""][..], "Lunch"),
+            (
+                &["porquinho", "--profile", "work", "take", "12", ""],
+                "Lunch",
+            ),
+            (
+                &["porquinho", "--profile", "=", "work", "put", "12", "L"],
+                "Lunch",
+            ),
+            (
+                &["porquinho", "-q", "take", "-c", "food", "12", ""],
+                "Lunch",
+            ),
+            (&["porquinho", "take", ""], "clap"),
+            (&["porquinho", "take", "12", "Lunch", ""], "clap"),
+            (&["porquinho", "--profile", "take", "12", ""], "clap"),
+        ];
+
+        for (words, expected) in cases {
+            assert_eq!(bash_completions(words), expected, "{words:?}");
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use std::{collections::HashMap, ops::Not, path::PathBuf};
+
+use fs_err as fs;
+use toml::value::{Table as TomlTable, Value as TomlValue};
+
+use super::{check_stored, editor::MonthEditor, Access, Month, Storage};
+use crate::{
+    diagnostic::SourceFile,
+    error::{Error, Result, TomlTypeCheck},
+    file::{create_file_if_not_existent, BookkeepingFile},
+    lock::{FileLock, LOCK_TIMEOUT},
+    migration::{self, CURRENT_FORMAT_VERSION, FORMAT_VERSION_KEY},
+    parser::{Operation, OperationType},
+    vault::Vault,
+};
+
+/// The default storage, which keeps each month in a TOML file named `MM-YYYY`
+/// inside of a folder
+pub struct TomlDirectory {
+    folder: PathBuf,
+    /// Encrypts the files, if the books are encrypted
+    vault: Vault,
+    /// Months loaded for writing, which stay locked until the storage is dropped
+    open: HashMap<BookkeepingFile, OpenMonth>,
+}
+
+/// A month file that is locked by this process
+struct OpenMonth {
+    editor: MonthEditor,
+    lock: FileLock,
+}
+
+impl TomlDirectory {
+    pub fn new(folder: impl Into<PathBuf>, vault: Vault) -> Self {
+        Self {
+            folder: folder.into(),
+            vault,
+            open: HashMap::new(),
+        }
+    }
+
+    /// The path of the file where `month` is stored
+    pub fn path_of(&self, month: &BookkeepingFile) -> PathBuf {
+        self.folder.join(month.as_path())
+    }
+
+    /// Where the record of `expected`, the operation at `index` of `month`, is in its file,
+    /// as the key of its array of tables and its position in it
+    ///
+    /// The month is read again under its lock, so that it's checked against what's stored.
+    fn find_record(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+    ) -> Result<(&'static str, usize)> {
+        let operations = self.load_month(month, Access::ReadWrite)?.operations;
+        check_stored(month, operations.get(index), expected)?;
+
+        // Operations are loaded in the order of the records of each kind
+        let position = operations[..index]
+            .iter()
+            .filter(|operation| operation.kind == expected.kind)
+            .count();
+        let (array_key, _) = expected.kind.name_and_symbol();
+
+        Ok((array_key, position))
+    }
+
+    /// Applies `edit` to `month`, which must be loaded for writing, and writes it to its file
+    fn edit(&mut self, month: &BookkeepingFile, edit: impl FnOnce(&mut MonthEditor)) -> Result<()> {
+        let path = self.path_of(month);
+        // Safety: loaded for writing by the callers
+        let open = self.open.get_mut(month).unwrap();
+
+        edit(&mut open.editor);
+
+        self.vault
+            .write_atomically(&path, &open.editor.to_string())?;
+        log::info!("updated {}", path.display());
+
+        Ok(())
+    }
+}
+
+impl Storage for TomlDirectory {
+    fn load_month(&mut self, month: &BookkeepingFile, access: Access) -> Result<Month> {
+        let path = self.path_of(month);
+        let was_open = self.open.contains_key(month);
+        if access == Access::ReadWrite {
+            create_file_if_not_existent(&path);
+        } else if was_open.not() && path.exists().not() {
+            // Reading a month that was never written to leaves no file behind
+            return Ok(Month::default());
+        }
+
+        // Must be taken before reading, so that we don't read contents that are about to change
+        let mut lock = match self.open.remove(month) {
+            // Locking a month twice from the same process would wait forever
+            Some(open) => Some(open.lock),
+            None if access == Access::ReadWrite => Some(FileLock::acquire(&path, LOCK_TIMEOUT)?),
+            None => None,
+        };
+
+        let mut contents = self.vault.read_to_string(&path)?;
+
+        if migration::is_outdated(&SourceFile::new(&path, &contents))? {
+            if access == Access::Peek {
+                // Upgraded in memory only, the file is left for the next command that writes to it
+                let source = SourceFile::new(&path, &contents);
+                if let Some(version) = migration::format_version(&source)? {
+                    contents = migration::migrate(&source, version)?;
+                }
+            } else {
+                // Upgrading writes to the file, so it must be locked even if only reading it
+                if lock.is_none() {
+                    lock = Some(FileLock::acquire(&path, LOCK_TIMEOUT)?);
+                    contents = self.vault.read_to_string(&path)?;
+                }
+
+                contents =
+                    migration::migrate_file(&SourceFile::new(&path, &contents), &self.vault)?;
+            }
+        }
+
+        let source = SourceFile::new(&path, &contents);
+        let table = load_toml_table_or_default(&source)?;
+
+        let type_check_diagnosis = type_check_toml_fields(&table).into_diagnosis();
+        if type_check_diagnosis.has_error_description() {
+            return Err(Error::InvalidTomlTypes {
+                description: type_check_diagnosis.into_inner(),
+                path,
+            });
+        }
+
+        let operations = operations_from_toml_table(&table, &source)?;
+        let target = table.get("target").and_then(TomlValue::as_integer);
+
+        // Locks taken only to upgrade the file are released once it's read
+        if let Some(lock) = lock.filter(|_| access == Access::ReadWrite || was_open) {
+            let editor = load_editor_or_default(&contents)?;
+            self.open.insert(*month, OpenMonth { editor, lock });
+        }
+
+        Ok(Month { operations, target })
+    }
+
+    fn append_operation(&mut self, month: &BookkeepingFile, operation: &Operation) -> Result<()> {
+        if self.open.contains_key(month).not() {
+            self.load_month(month, Access::ReadWrite)?;
+        }
+
+        let (array_key, _) = operation.kind.name_and_symbol();
+        self.edit(month, |editor| editor.push_record(array_key, operation))
+    }
+
+    fn replace_operation(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+        operation: &Operation,
+    ) -> Result<()> {
+        let (array_key, position) = self.find_record(month, index, expected)?;
+
+        self.edit(month, |editor| {
+            editor.replace_record(array_key, position, operation)
+        })
+    }
+
+    fn remove_operation(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+    ) -> Result<()> {
+        let (array_key, position) = self.find_record(month, index, expected)?;
+
+        self.edit(month, |editor| editor.remove_record(array_key, position))
+    }
+
+    fn set_target(&mut self, month: &BookkeepingFile, target: Option<i64>) -> Result<()> {
+        if self.open.contains_key(month).not() {
+            self.load_month(month, Access::ReadWrite)?;
+        }
+
+        self.edit(month, |editor| editor.set_target(target))
+    }
+
+    /// Files whose names are not in the `MM-YYYY` format, such as the backups
+    /// and locks kept next to month files, are ignored.
+    fn list_months(&self) -> Result<Vec<BookkeepingFile>> {
+        let mut months = vec![];
+
+        for entry in fs::read_dir(&self.folder)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let month = file_name.to_str().and_then(BookkeepingFile::from_file_name);
+
+            if let Some(month) = month {
+                if entry.file_type()?.is_file() {
+                    months.push(month);
+                }
+            }
+        }
+
+        months.sort_by_key(|month| (month.year(), month.month()));
+
+        Ok(months)
+    }
+
+    /// The file is written anew, without the comments of the previous one
+    fn write_month(&mut self, month: &BookkeepingFile, data: &Month) -> Result<()> {
+        let path = self.path_of(month);
+        let lock = match self.open.remove(month) {
+            Some(open) => open.lock,
+            None => FileLock::acquire(&path, LOCK_TIMEOUT)?,
+        };
+
+        let mut header = format!("{FORMAT_VERSION_KEY} = {CURRENT_FORMAT_VERSION}\n");
+        if let Some(target) = data.target {
+            header.push_str(&format!("target = {target}\n"));
+        }
+
+        let mut editor = MonthEditor::parse(&header)?;
+        for operation in &data.operations {
+            let (array_key, _) = operation.kind.name_and_symbol();
+            editor.push_record(array_key, operation);
+        }
+
+        self.vault.write_atomically(&path, &editor.to_string())?;
+        self.open.insert(*month, OpenMonth { editor, lock });
+
+        Ok(())
+    }
+}
+
+fn load_toml_table_or_default(source: &SourceFile) -> Result<TomlTable> {
+    let toml = if source.contents.trim().is_empty() {
+        generate_default_toml()
+    } else {
+        source
+            .contents
+            .parse()
+            .map_err(|err| source.diagnose_toml(&err))?
+    };
+
+    Ok(unwrap_toml_table(toml))
+}
+
+fn load_editor_or_default(input_text: &str) -> Result<MonthEditor> {
+    if input_text.trim().is_empty() {
+        MonthEditor::parse(&generate_default_toml().to_string())
+    } else {
+        MonthEditor::parse(input_text)
+    }
+}
+
+fn operations_from_toml_table(table: &TomlTable, source: &SourceFile) -> Result<Vec<Operation>> {
+    let mut operations = vec![];
+
+    for kind in [OperationType::Withdraw, OperationType::Deposit] {
+        let (array_key, _) = kind.name_and_symbol();
+        let records = table
+            .get(array_key)
+            .and_then(TomlValue::as_array)
+            .map_or(&[][..], Vec::as_slice);
+
+        for (index, record) in records.iter().enumerate() {
+            // Safety: type checked when loaded
+            let record = record.as_table().unwrap();
+            let operation = Operation::from_record(kind.clone(), record)
+                .map_err(|err| source.diagnose_record(array_key, index, &err))?;
+
+            operations.push(operation);
+        }
+    }
+
+    Ok(operations)
+}
+
+/// Checks the types of the fields of a bookkeeping file in the current format
+///
+/// Months without operations of a kind don't have an array for them.
+pub(crate) fn type_check_toml_fields(table: &TomlTable) -> TomlTypeCheck {
+    let is_array_or_undefined = |key| table.get(key).is_none_or(TomlValue::is_array);
+
+    let is_take_array_or_undefined = is_array_or_undefined("take");
+    let is_put_array_or_undefined = is_array_or_undefined("put");
+    let is_target_int_or_undefined = table.get("target").map_or(true, TomlValue::is_integer);
+
+    let is_array_of_tables = |key| {
+        table
+            .get(key)
+            .and_then(TomlValue::as_array)
+            .is_none_or(|array| array.iter().all(TomlValue::is_table))
+    };
+
+    TomlTypeCheck {
+        is_take_array_or_undefined,
+        is_put_array_or_undefined,
+        is_target_int_or_undefined,
+        is_take_array_of_tables: is_array_of_tables("take"),
+        is_put_array_of_tables: is_array_of_tables("put"),
+    }
+}
+
+/// The contents of a month without operations, whose arrays are created as they're added
+fn generate_default_toml() -> TomlValue {
+    let mut table = TomlTable::new();
+    table.insert(FORMAT_VERSION_KEY.into(), CURRENT_FORMAT_VERSION.into());
+
+    TomlValue::Table(table)
+}
+
+fn unwrap_toml_table(toml: TomlValue) -> TomlTable {
+    match toml {
+        TomlValue::Table(table) => table,
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Not, str::FromStr, time::Duration};
+
+    use bigdecimal::BigDecimal;
+    use fs_err as fs;
+
+    use super::TomlDirectory;
+    use crate::{
+        file::BookkeepingFile,
+        lock::FileLock,
+        migration::CURRENT_FORMAT_VERSION,
+        parser::{Operation, OperationType},
+        storage::{Access, Month, Storage},
+        vault::Vault,
+        Error,
+    };
+
+    fn october() -> BookkeepingFile {
+        BookkeepingFile::from_file_name("10-2024").unwrap()
+    }
+
+    fn lunch() -> Operation {
+        Operation::new(3, OperationType::Withdraw, BigDecimal::from(12), "Lunch")
+    }
+
+    #[test]
+    fn keeps_previous_version_as_backup_when_appending() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("10-2024");
+        fs::write(&path, "format_version = 2\n").unwrap();
+
+        let mut storage = TomlDirectory::new(dir.path(), Vault::default());
+        storage.append_operation(&october(), &lunch()).unwrap();
+        drop(storage);
+
+        let month = TomlDirectory::new(dir.path(), Vault::default())
+            .load_month(&october(), Access::ReadOnly)
+            .unwrap();
+        assert_eq!(month.operations, [lunch()]);
+
+        let backup = fs::read_to_string(dir.path().join("10-2024.bak")).unwrap();
+        assert_eq!(backup, "format_version = 2\n");
+        assert!(dir.path().join("10-2024.tmp").exists().not());
+    }
+
+    #[test]
+    fn refuses_to_write_while_another_process_holds_the_month() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("10-2024");
+
+        let mut storage = TomlDirectory::new(dir.path(), Vault::default());
+        storage.load_month(&october(), Access::ReadWrite).unwrap();
+        // Loading again from the same storage doesn't wait for itself
+        storage.load_month(&october(), Access::ReadWrite).unwrap();
+
+        let contender = FileLock::acquire(&path, Duration::from_millis(100));
+        assert!(matches!(contender, Err(Error::Locked(_))));
+
+        // Readers are never blocked
+        TomlDirectory::new(dir.path(), Vault::default())
+            .load_month(&october(), Access::ReadOnly)
+            .unwrap();
+
+        drop(storage);
+        FileLock::acquire(&path, Duration::ZERO).unwrap();
+    }
+
+    #[test]
+    fn reports_malformed_operations_instead_of_panicking() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("10-2024"),
+            "format_version = 2\n\n[[take]]\nday = 23\namount = \"10,25\"\ndescription = \"Lunch\"\n",
+        )
+        .unwrap();
+
+        let err = TomlDirectory::new(dir.path(), Vault::default())
+            .load_month(&october(), Access::ReadOnly)
+            .unwrap_err();
+
+        let diagnostic = match err {
+            Error::Diagnostic(diagnostic) => diagnostic,
+            other => panic!("expected a diagnostic, got {other:?}"),
+        };
+        assert_eq!((diagnostic.line, diagnostic.column), (5, 11));
+        assert_eq!(diagnostic.source_line, "amount = \"10,25\"");
+    }
+
+    #[test]
+    fn upgrades_outdated_files_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("10-2024");
+        fs::write(&path, "put = []\ntake = [\"3 - 5 Bus\"]\n").unwrap();
+
+        let month = TomlDirectory::new(dir.path(), Vault::default())
+            .load_month(&october(), Access::ReadOnly)
+            .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with(&format!("format_version = {CURRENT_FORMAT_VERSION}\n")));
+        assert!(dir.path().join("10-2024.v0.bak").exists());
+        assert_eq!(month.operations[0].amount, BigDecimal::from(5));
+    }
+
+    #[test]
+    fn reading_leaves_no_files_or_locks_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = TomlDirectory::new(dir.path(), Vault::default());
+
+        let month = storage.load_month(&october(), Access::ReadOnly).unwrap();
+        assert_eq!(month, Month::default());
+        assert!(dir.path().join("10-2024").exists().not());
+
+        // Upgrading an outdated month while reading it locks it only for as long as needed
+        let path = dir.path().join("10-2024");
+        fs::write(&path, "take = [\"3 - 5 Bus\"]\n").unwrap();
+        storage.load_month(&october(), Access::ReadOnly).unwrap();
+        FileLock::acquire(&path, Duration::ZERO).unwrap();
+    }
+
+    #[test]
+    fn peeking_leaves_outdated_files_as_they_are() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("10-2024");
+        let contents = "take = [\"3 - 5 Bus\"]\n";
+        fs::write(&path, contents).unwrap();
+
+        let month = TomlDirectory::new(dir.path(), Vault::default())
+            .load_month(&october(), Access::Peek)
+            .unwrap();
+
+        assert_eq!(month.operations[0].amount, BigDecimal::from(5));
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+        assert!(dir.path().join("10-2024.v0.bak").exists().not());
+    }
+
+    #[test]
+    fn writes_and_lists_months() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("README.md"), "# Ledger").unwrap();
+
+        let mut salary = Operation::new(
+            1,
+            OperationType::Deposit,
+            BigDecimal::from_str("3000.50").unwrap(),
+            "Salary",
+        );
+        salary.category = Some("work".into());
+        let month = Month {
+            operations: vec![lunch(), salary],
+            target: Some(1000),
+        };
+
+        let mut storage = TomlDirectory::new(dir.path(), Vault::default());
+        let march = BookkeepingFile::from_file_name("03-2025").unwrap();
+        storage.write_month(&march, &month).unwrap();
+        storage.write_month(&october(), &Month::default()).unwrap();
+
+        assert_eq!(storage.list_months().unwrap(), [october(), march]);
+        assert_eq!(storage.load_month(&march, Access::ReadOnly).unwrap(), month);
+    }
+
+    #[test]
+    fn edits_operations_in_place_keeping_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("10-2024");
+        fs::write(
+            &path,
+            r#"format_version = 2
+target = 1000 # tight month
+
+# Work lunches
+[[take]]
+day = 3
+amount = "12"
+description = "Lunch" # with Ana
+
+[[take]]
+day = 5
+amount = "30"
+description = "Uber"
+
+[[put]]
+day = 1
+amount = "3000"
+description = "Salary"
+"#,
+        )
+        .unwrap();
+
+        let mut storage = TomlDirectory::new(dir.path(), Vault::default());
+        let operations = storage
+            .load_month(&october(), Access::ReadOnly)
+            .unwrap()
+            .operations;
+
+        let mut dinner = operations[0].clone();
+        dinner.description = "Dinner".into();
+        dinner.category = Some("food".into());
+        storage
+            .replace_operation(&october(), 0, &operations[0], &dinner)
+            .unwrap();
+        storage
+            .remove_operation(&october(), 2, &operations[2])
+            .unwrap();
+        storage.set_target(&october(), Some(1200)).unwrap();
+
+        // The index no longer holds the salary, which was removed
+        let stale = storage.remove_operation(&october(), 2, &operations[2]);
+        assert!(matches!(stale, Err(Error::OperationChanged(_))));
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            r#"format_version = 2
+target = 1200 # tight month
+
+# Work lunches
+[[take]]
+day = 3
+amount = "12"
+description = "Dinner" # with Ana
+category = "food"
+
+[[take]]
+day = 5
+amount = "30"
+description = "Uber"
+"#
+        );
+    }
+
+    #[test]
+    fn moves_operations_that_change_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = TomlDirectory::new(dir.path(), Vault::default());
+        storage.append_operation(&october(), &lunch()).unwrap();
+        storage.set_target(&october(), Some(500)).unwrap();
+
+        let mut refund = lunch();
+        refund.kind = OperationType::Deposit;
+        storage
+            .replace_operation(&october(), 0, &lunch(), &refund)
+            .unwrap();
+        storage.set_target(&october(), None).unwrap();
+
+        let month = storage.load_month(&october(), Access::ReadOnly).unwrap();
+        assert_eq!(month.operations, [refund]);
+        assert_eq!(month.target, None);
+    }
+}
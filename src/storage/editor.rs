@@ -0,0 +1,333 @@
+use std::fmt;
+
+use toml_edit::{value, Array, ArrayOfTables, Document, Item, Table, Value};
+
+use crate::{
+    parser::{format_time, Operation, RECORD_FIELDS},
+    Result,
+};
+
+/// Edits the text of a bookkeeping file in place, keeping the comments,
+/// ordering and formatting written by the user intact
+pub(super) struct MonthEditor {
+    document: Document,
+}
+
+impl MonthEditor {
+    pub fn parse(contents: &str) -> Result<Self> {
+        let document = contents.parse()?;
+
+        Ok(Self { document })
+    }
+
+    /// Appends `operation` as a record after the last one of the `array_key` array of tables
+    ///
+    /// The array is created if missing, which is guaranteed to be fine by the type checks
+    /// done when loading the file.
+    pub fn push_record(&mut self, array_key: &str, operation: &Operation) {
+        let item = &mut self.document[array_key];
+
+        // Months without operations of a kind may have it as `take = []`
+        let is_empty_array = item.as_array().is_some_and(Array::is_empty);
+        if item.is_none() || is_empty_array {
+            *item = Item::ArrayOfTables(ArrayOfTables::new());
+
+            // The space between the key and the `=` would end up inside of the headers
+            if let Some(decor) = self.document.key_decor_mut(array_key) {
+                decor.set_suffix("");
+            }
+        }
+
+        self.document[array_key]
+            .as_array_of_tables_mut()
+            .expect("array was type checked on load")
+            .push(operation_record(operation));
+    }
+
+    /// Changes the record at `position` of the `array_key` array of tables to hold `operation`
+    ///
+    /// The comments around the record and its fields are kept, as are fields porquinho
+    /// doesn't know about. If `operation` is of another kind, it's moved to the end of
+    /// the array of its kind instead.
+    pub fn replace_record(&mut self, array_key: &str, position: usize, operation: &Operation) {
+        let (new_array_key, _) = operation.kind.name_and_symbol();
+        if new_array_key != array_key {
+            self.remove_record(array_key, position);
+            self.push_record(new_array_key, operation);
+            return;
+        }
+
+        let record = self.document[array_key]
+            .as_array_of_tables_mut()
+            .and_then(|records| records.get_mut(position))
+            .expect("record was checked to be stored");
+        let mut fields = operation_record(operation);
+
+        for field in RECORD_FIELDS {
+            match fields.remove(field).and_then(|item| item.into_value().ok()) {
+                Some(value) => set_value(&mut record[field], value),
+                None => {
+                    record.remove(field);
+                }
+            }
+        }
+    }
+
+    /// Removes the record at `position` of the `array_key` array of tables, along with
+    /// the comments right above it
+    pub fn remove_record(&mut self, array_key: &str, position: usize) {
+        self.document[array_key]
+            .as_array_of_tables_mut()
+            .expect("record was checked to be stored")
+            .remove(position);
+    }
+
+    /// Sets the most that is meant to be spent in the month, removing it if `None`
+    pub fn set_target(&mut self, target: Option<i64>) {
+        match target {
+            Some(target) => set_value(&mut self.document["target"], target.into()),
+            None => {
+                self.document.remove("target");
+            }
+        }
+    }
+}
+
+/// Puts `new_value` in `item`, keeping the comments and spacing around the value it replaces
+fn set_value(item: &mut Item, mut new_value: Value) {
+    if let Some(old_value) = item.as_value() {
+        *new_value.decor_mut() = old_value.decor().clone();
+    }
+
+    *item = Item::Value(new_value);
+}
+
+/// The record an operation is stored as, with its fields in the order of `RECORD_FIELDS`
+pub(crate) fn operation_record(operation: &Operation) -> Table {
+    let mut record = Table::new();
+
+    record["day"] = value(i64::from(operation.day));
+    // Written as a string so that it's read back without losing precision
+    record["amount"] = value(operation.amount.to_string());
+    record["description"] = value(operation.description.as_str());
+
+    if let Some(time) = operation.time {
+        record["time"] = value(format_time(time));
+    }
+
+    let optional_fields = [
+        ("category", &operation.category),
+        ("currency", &operation.currency),
+        ("id", &operation.id),
+    ];
+    for (field, text) in optional_fields {
+        if let Some(text) = text {
+            record[field] = value(text.as_str());
+        }
+    }
+
+    record
+}
+
+/// `operation` written the way it's stored in a bookkeeping file, e.g. to preview
+/// what is added to one
+pub fn record_text(operation: &Operation) -> String {
+    let (name, _) = operation.kind.name_and_symbol();
+    let mut records = ArrayOfTables::new();
+    records.push(operation_record(operation));
+
+    let mut document = Document::new();
+    document[name] = Item::ArrayOfTables(records);
+
+    // Headers are written after a blank line, which only matters within a file
+    document.to_string().trim_start().to_owned()
+}
+
+/// A string element of an array, along with the comments around it
+#[derive(Debug, Clone)]
+pub(crate) struct ArrayEntry {
+    pub text: String,
+    /// Comment lines right above the element, e.g. `"    # Groceries\n"`
+    leading: String,
+    /// Comment placed after the element in the same line, e.g. `" # with Ana"`
+    trailing: String,
+}
+
+impl ArrayEntry {
+    /// The comments around the element, one per line, without their indentation
+    pub fn comments(&self) -> Vec<String> {
+        comment_lines(&self.leading)
+            .chain(comment_lines(&self.trailing))
+            .collect()
+    }
+}
+
+/// The lines of `text` that are comments, without the whitespace around them
+pub(crate) fn comment_lines(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('#'))
+        .map(str::to_owned)
+}
+
+/// Splits the string elements of `array` into entries
+///
+/// In TOML, a comment after an element is part of the whitespace that comes before
+/// the next one, which is why each entry is pieced together from its neighbours.
+pub(crate) fn take_entries(array: &Array) -> Vec<ArrayEntry> {
+    let values: Vec<&Value> = array.iter().collect();
+
+    let mut entries = vec![];
+    for (index, value) in values.iter().enumerate() {
+        let text = match value.as_str() {
+            Some(text) => text,
+            None => continue,
+        };
+
+        let (_, leading) = split_first_line(prefix_of(value));
+
+        let next_prefix = values
+            .get(index + 1)
+            .map_or(array.trailing(), |next| prefix_of(next));
+        let (trailing, _) = split_first_line(next_prefix);
+
+        entries.push(ArrayEntry {
+            text: text.to_owned(),
+            leading: leading.to_owned(),
+            trailing: trailing.to_owned(),
+        });
+    }
+
+    entries
+}
+
+/// The comments of `array` that aren't next to any of its elements, such as
+/// one after its opening bracket or right before its closing one
+pub(crate) fn loose_comments(array: &Array) -> Vec<String> {
+    let first = match array.iter().next() {
+        Some(first) => first,
+        // Everything inside of an empty array is loose
+        None => return comment_lines(array.trailing()).collect(),
+    };
+
+    let (opening, _) = split_first_line(prefix_of(first));
+    let (_, closing) = split_first_line(array.trailing());
+
+    comment_lines(opening)
+        .chain(comment_lines(closing))
+        .collect()
+}
+
+fn prefix_of(value: &Value) -> &str {
+    value.decor().prefix().unwrap_or_default()
+}
+
+/// Splits `text` at its first newline, if any
+fn split_first_line(text: &str) -> (&str, &str) {
+    text.split_once('\n').unwrap_or(("", text))
+}
+
+impl fmt::Display for MonthEditor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use toml_edit::Document;
+
+    use super::{loose_comments, record_text, take_entries, MonthEditor};
+    use crate::parser::{Operation, OperationType};
+
+    fn bus() -> Operation {
+        let amount = BigDecimal::from_str("15.50").unwrap();
+        Operation::new(6, OperationType::Withdraw, amount, "Bus")
+    }
+
+    #[test]
+    fn appends_records_after_their_siblings_keeping_comments() {
+        let contents = r#"format_version = 2
+target = 3000 # for now
+
+# Groceries are on the credit card
+[[take]]
+day = 3
+amount = "12.00"
+description = "Lunch" # with Ana
+
+[[put]]
+day = 1
+amount = "3000"
+description = "Salary"
+"#;
+
+        let mut editor = MonthEditor::parse(contents).unwrap();
+        editor.push_record("take", &bus());
+
+        let expected = contents.replace(
+            "# with Ana\n",
+            "# with Ana\n\n[[take]]\nday = 6\namount = \"15.50\"\ndescription = \"Bus\"\n",
+        );
+        assert_eq!(editor.to_string(), expected);
+    }
+
+    #[test]
+    fn appends_to_missing_and_empty_arrays() {
+        let mut bus = bus();
+        bus.time = chrono::NaiveTime::from_hms_opt(7, 45, 0);
+        bus.category = Some("transport".into());
+
+        let mut editor = MonthEditor::parse("format_version = 2\ntake = []\n").unwrap();
+        editor.push_record("take", &bus);
+
+        assert_eq!(
+            editor.to_string(),
+            "format_version = 2\n\n[[take]]\nday = 6\namount = \"15.50\"\ndescription = \"Bus\"\ntime = \"07:45\"\ncategory = \"transport\"\n"
+        );
+    }
+
+    #[test]
+    fn previews_records_the_way_they_are_read() {
+        let mut lunch = Operation::new(3, OperationType::Withdraw, BigDecimal::from(12), "Lunch");
+        lunch.category = Some("food".into());
+
+        let text = record_text(&lunch);
+        assert!(text.starts_with("[[take]]\nday = 3\n"));
+
+        let table: toml::value::Table = toml::from_str(&text).unwrap();
+        let record = table["take"][0].as_table().unwrap();
+        assert_eq!(
+            Operation::from_record(OperationType::Withdraw, record).unwrap(),
+            lunch
+        );
+    }
+
+    #[test]
+    fn keeps_the_comments_around_array_elements() {
+        let contents = r#"take = [ # October
+  # Weekdays
+  "3 - 12.00 Lunch", # with Ana
+  'weird \ one',
+  "1 - 4.00 Bus", # late
+]
+"#;
+
+        let document: Document = contents.parse().unwrap();
+        let entries = take_entries(document["take"].as_array().unwrap());
+
+        let comments: Vec<_> = entries.iter().map(|entry| entry.comments()).collect();
+        assert_eq!(
+            comments,
+            [vec!["# Weekdays", "# with Ana"], vec![], vec!["# late"]]
+        );
+        assert_eq!(entries[1].text, "weird \\ one");
+        assert_eq!(
+            loose_comments(document["take"].as_array().unwrap()),
+            ["# October"]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+#[cfg(test)]
+use bigdecimal::BigDecimal;
+
+use super::{check_stored, Access, Month, Storage};
+#[cfg(test)]
+use crate::parser::OperationType;
+use crate::{file::BookkeepingFile, parser::Operation, Result};
+
+/// Keeps months in memory, for tests and programs that don't need to touch the file system
+#[derive(Default)]
+pub struct MemoryStorage {
+    months: HashMap<BookkeepingFile, Month>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+impl MemoryStorage {
+    /// Storage holding `operations`, written down as `(month, day, kind, amount, description)`
+    /// and appended in the order they're given
+    pub(crate) fn with_operations(operations: &[(&str, u8, OperationType, u32, &str)]) -> Self {
+        let mut storage = Self::new();
+        for (month, day, kind, amount, description) in operations {
+            let month = BookkeepingFile::from_file_name(month).unwrap();
+            let operation =
+                Operation::new(*day, kind.clone(), BigDecimal::from(*amount), description);
+            storage.append_operation(&month, &operation).unwrap();
+        }
+
+        storage
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load_month(&mut self, month: &BookkeepingFile, _access: Access) -> Result<Month> {
+        Ok(self.months.get(month).cloned().unwrap_or_default())
+    }
+
+    fn append_operation(&mut self, month: &BookkeepingFile, operation: &Operation) -> Result<()> {
+        let month = self.months.entry(*month).or_default();
+        month.operations.push(operation.clone());
+
+        Ok(())
+    }
+
+    fn replace_operation(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+        operation: &Operation,
+    ) -> Result<()> {
+        let data = self.months.entry(*month).or_default();
+        check_stored(month, data.operations.get(index), expected)?;
+        data.operations[index] = operation.clone();
+
+        Ok(())
+    }
+
+    fn remove_operation(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+    ) -> Result<()> {
+        let data = self.months.entry(*month).or_default();
+        check_stored(month, data.operations.get(index), expected)?;
+        data.operations.remove(index);
+
+        Ok(())
+    }
+
+    fn set_target(&mut self, month: &BookkeepingFile, target: Option<i64>) -> Result<()> {
+        self.months.entry(*month).or_default().target = target;
+
+        Ok(())
+    }
+
+    fn list_months(&self) -> Result<Vec<BookkeepingFile>> {
+        let mut months: Vec<_> = self.months.keys().copied().collect();
+        months.sort_by_key(|month| (month.year(), month.month()));
+
+        Ok(months)
+    }
+
+    fn write_month(&mut self, month: &BookkeepingFile, data: &Month) -> Result<()> {
+        self.months.insert(*month, data.clone());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,302 @@
+mod directory;
+pub(crate) mod editor;
+mod memory;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::path::Path;
+
+use fs_err as fs;
+
+pub use directory::TomlDirectory;
+pub use editor::record_text;
+pub use memory::MemoryStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+pub(crate) use directory::type_check_toml_fields;
+
+use crate::{
+    file::{sibling_path, BookkeepingFile},
+    parser::Operation,
+    vault::Vault,
+    Error, Result,
+};
+
+/// Name of the database kept in the data folder by the SQLite storage
+pub const DATABASE_FILE_NAME: &str = "porquinho.sqlite3";
+
+/// The kinds of storage the books can be kept in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// A TOML file per month, the default
+    Toml,
+    /// A single SQLite database, if porquinho was built with the `sqlite` feature
+    Sqlite,
+}
+
+impl Backend {
+    /// The backend the books in `folder` are kept in
+    pub fn in_use(folder: &Path) -> Self {
+        if folder.join(DATABASE_FILE_NAME).exists() {
+            Self::Sqlite
+        } else {
+            Self::Toml
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Toml => "TOML files",
+            Self::Sqlite => "SQLite",
+        }
+    }
+}
+
+/// Opens the storage the books in `folder` are kept in, whose files are
+/// read and written through `vault`
+pub fn open(folder: &Path, vault: Vault) -> Result<Box<dyn Storage>> {
+    let backend = Backend::in_use(folder);
+    log::debug!(
+        "the books in {} are stored in {}",
+        folder.display(),
+        backend.name()
+    );
+
+    match backend {
+        Backend::Toml => Ok(Box::new(TomlDirectory::new(folder, vault))),
+        Backend::Sqlite => open_database(&folder.join(DATABASE_FILE_NAME)),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn open_database(path: &Path) -> Result<Box<dyn Storage>> {
+    Ok(Box::new(SqliteStorage::open(path)?))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn open_database(_path: &Path) -> Result<Box<dyn Storage>> {
+    Err(Error::SqliteUnsupported)
+}
+
+/// Moves the books in `folder` to the `to` backend, returning how many months were moved
+///
+/// Every field of every operation is kept, though comments in TOML files are not.
+/// What's left in the previous backend is renamed with a `.migrated` extension, so that
+/// it can't be changed by mistake but can still be recovered.
+///
+/// Encrypted books can only be kept in TOML files, which are read and written through `vault`.
+pub fn migrate_books(folder: &Path, to: Backend, vault: Vault) -> Result<usize> {
+    let database_path = folder.join(DATABASE_FILE_NAME);
+
+    match (Backend::in_use(folder), to) {
+        (Backend::Toml, Backend::Toml) => Err(Error::AlreadyStoredIn(folder.to_owned())),
+        (Backend::Sqlite, Backend::Sqlite) => Err(Error::AlreadyStoredIn(database_path)),
+        (Backend::Toml, Backend::Sqlite) if vault.is_encrypting() => Err(Error::EncryptedSqlite),
+        (Backend::Toml, Backend::Sqlite) => {
+            // Built aside and moved in place once complete, so that a failure
+            // midway doesn't leave the books split between both backends
+            let temp_path = sibling_path(&database_path, "tmp");
+            if temp_path.exists() {
+                fs::remove_file(&temp_path)?;
+            }
+
+            let mut files = TomlDirectory::new(folder, vault);
+            let months = copy_months(&mut files, open_database(&temp_path)?.as_mut())?;
+            fs::rename(&temp_path, &database_path)?;
+
+            for month in &months {
+                let path = files.path_of(month);
+                fs::rename(&path, sibling_path(&path, "migrated"))?;
+            }
+
+            Ok(months.len())
+        }
+        (Backend::Sqlite, Backend::Toml) => {
+            let mut database = open_database(&database_path)?;
+            let months = copy_months(database.as_mut(), &mut TomlDirectory::new(folder, vault))?;
+            drop(database);
+
+            fs::rename(&database_path, sibling_path(&database_path, "migrated"))?;
+
+            Ok(months.len())
+        }
+    }
+}
+
+/// Writes every month of `from` into `to`, returning the months copied
+///
+/// The months are held for writing in `from` until it is dropped, so that
+/// nothing is added to them while they're being moved.
+fn copy_months(from: &mut dyn Storage, to: &mut dyn Storage) -> Result<Vec<BookkeepingFile>> {
+    let months = from.list_months()?;
+
+    for month in &months {
+        let data = from.load_month(month, Access::ReadWrite)?;
+        to.write_month(month, &data)?;
+    }
+
+    Ok(months)
+}
+
+/// Fails unless `expected` is the operation `stored` at some index of `month`
+fn check_stored(
+    month: &BookkeepingFile,
+    stored: Option<&Operation>,
+    expected: &Operation,
+) -> Result<()> {
+    if stored == Some(expected) {
+        Ok(())
+    } else {
+        Err(Error::OperationChanged(month.as_str().to_owned()))
+    }
+}
+
+/// What a command intends to do with a month
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// The month is only read, so other processes are free to change it meanwhile
+    ReadOnly,
+    /// The month may be changed, so it's locked against other porquinho processes
+    ReadWrite,
+    /// The month is read as it is, without writing to it even to upgrade it, for
+    /// what only glances over the books, such as suggestions
+    Peek,
+}
+
+/// Everything stored for a month
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct Month {
+    /// Operations in the order they were stored
+    pub operations: Vec<Operation>,
+    pub target: Option<i64>,
+}
+
+/// Where bookkeeping data is kept
+pub trait Storage {
+    /// Loads what is stored for `month`, which is empty if nothing is stored yet
+    ///
+    /// With `Access::ReadWrite`, the month is held against changes from other
+    /// porquinho processes until the storage is dropped.
+    fn load_month(&mut self, month: &BookkeepingFile, access: Access) -> Result<Month>;
+
+    /// Stores `operation` in `month`, after the operations already there
+    fn append_operation(&mut self, month: &BookkeepingFile, operation: &Operation) -> Result<()>;
+
+    /// Replaces `expected`, the operation at `index` of the ones loaded for `month`,
+    /// with `operation`
+    ///
+    /// Fails with `Error::OperationChanged` if `expected` is no longer at `index`,
+    /// e.g. because another porquinho process changed the month since it was loaded.
+    fn replace_operation(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+        operation: &Operation,
+    ) -> Result<()>;
+
+    /// Removes `expected`, the operation at `index` of the ones loaded for `month`
+    ///
+    /// Fails with `Error::OperationChanged` if `expected` is no longer at `index`.
+    fn remove_operation(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+    ) -> Result<()>;
+
+    /// Sets the most that is meant to be spent in `month`, removing it if `None`
+    fn set_target(&mut self, month: &BookkeepingFile, target: Option<i64>) -> Result<()>;
+
+    /// Every month with something stored, oldest first
+    fn list_months(&self) -> Result<Vec<BookkeepingFile>>;
+
+    /// Replaces what is stored for `month` with `data` atomically, so that a
+    /// failure midway leaves the previous data in place
+    ///
+    /// Meant for moving the books between storages, since it may not keep what
+    /// `data` doesn't hold, such as the comments of TOML files.
+    fn write_month(&mut self, month: &BookkeepingFile, data: &Month) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{migrate_books, Backend};
+    use crate::{vault::Vault, Error};
+
+    #[test]
+    fn refuses_to_migrate_to_the_backend_in_use() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(matches!(
+            migrate_books(dir.path(), Backend::Toml, Vault::default()),
+            Err(Error::AlreadyStoredIn(_))
+        ));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn migrates_books_back_and_forth_losslessly() {
+        use fs_err as fs;
+
+        use super::{open, DATABASE_FILE_NAME};
+        use crate::{file::BookkeepingFile, storage::Access};
+
+        let dir = tempfile::tempdir().unwrap();
+        let contents = r#"format_version = 2
+target = 1000
+
+[[take]]
+day = 3
+amount = "12.50"
+description = "Lunch"
+time = "12:30"
+category = "food"
+
+[[put]]
+day = 1
+amount = "3000.00"
+description = "Salary"
+currency = "USD"
+id = "tx-1"
+"#;
+        fs::write(dir.path().join("10-2024"), contents).unwrap();
+        fs::write(dir.path().join("09-2024"), "format_version = 2\n").unwrap();
+        let october = BookkeepingFile::from_file_name("10-2024").unwrap();
+        let before = open(dir.path(), Vault::default())
+            .unwrap()
+            .load_month(&october, Access::ReadOnly)
+            .unwrap();
+
+        assert_eq!(
+            migrate_books(dir.path(), Backend::Sqlite, Vault::default()).unwrap(),
+            2
+        );
+        assert_eq!(Backend::in_use(dir.path()), Backend::Sqlite);
+        assert!(dir.path().join("10-2024.migrated").exists());
+
+        let mut storage = open(dir.path(), Vault::default()).unwrap();
+        assert_eq!(storage.list_months().unwrap().len(), 2);
+        assert_eq!(
+            storage.load_month(&october, Access::ReadOnly).unwrap(),
+            before
+        );
+        drop(storage);
+
+        assert_eq!(
+            migrate_books(dir.path(), Backend::Toml, Vault::default()).unwrap(),
+            2
+        );
+        assert_eq!(Backend::in_use(dir.path()), Backend::Toml);
+        assert!(dir
+            .path()
+            .join(format!("{DATABASE_FILE_NAME}.migrated"))
+            .exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("10-2024")).unwrap(),
+            contents
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use std::{path::Path, str::FromStr};
+
+use bigdecimal::BigDecimal;
+use rusqlite::{params, Connection, OptionalExtension, Row, TransactionBehavior};
+
+use super::{check_stored, Access, Month, Storage};
+use crate::{
+    file::BookkeepingFile,
+    lock::LOCK_TIMEOUT,
+    parser::{format_time, parse_time, Operation, OperationType, ParseError},
+    Result,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS months (
+    year INTEGER NOT NULL,
+    month INTEGER NOT NULL,
+    target INTEGER,
+    PRIMARY KEY (year, month)
+);
+
+CREATE TABLE IF NOT EXISTS operations (
+    -- Keeps the order in which operations were stored
+    position INTEGER PRIMARY KEY,
+    year INTEGER NOT NULL,
+    month INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('take', 'put')),
+    day INTEGER NOT NULL,
+    -- Stored as text so that no precision is lost
+    amount TEXT NOT NULL,
+    description TEXT NOT NULL,
+    time TEXT,
+    category TEXT,
+    currency TEXT,
+    id TEXT,
+    FOREIGN KEY (year, month) REFERENCES months (year, month)
+);
+
+CREATE INDEX IF NOT EXISTS operations_by_month ON operations (year, month);
+";
+
+/// Keeps every month in a single SQLite database
+///
+/// SQLite takes care of concurrent writers on its own, so months are never
+/// held locked between reading and writing them.
+pub struct SqliteStorage {
+    connection: Connection,
+}
+
+impl SqliteStorage {
+    /// Opens the database at `path`, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        // Other porquinho processes writing at the same time are waited for
+        connection.busy_timeout(LOCK_TIMEOUT)?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self { connection })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load_month(&mut self, month: &BookkeepingFile, _access: Access) -> Result<Month> {
+        let key = (month.year(), month.month());
+
+        let target = self
+            .connection
+            .query_row(
+                "SELECT target FROM months WHERE year = ?1 AND month = ?2",
+                params![key.0, key.1],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+
+        let mut statement = self.connection.prepare(
+            "SELECT kind, day, amount, description, time, category, currency, id
+             FROM operations WHERE year = ?1 AND month = ?2 ORDER BY position",
+        )?;
+        let stored = statement
+            .query_map(params![key.0, key.1], StoredOperation::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let operations = stored
+            .into_iter()
+            .map(StoredOperation::into_operation)
+            .collect::<Result<_>>()?;
+
+        Ok(Month { operations, target })
+    }
+
+    fn append_operation(&mut self, month: &BookkeepingFile, operation: &Operation) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+
+        transaction.execute(
+            "INSERT OR IGNORE INTO months (year, month) VALUES (?1, ?2)",
+            params![month.year(), month.month()],
+        )?;
+        insert_operation(&transaction, month, operation)?;
+
+        transaction.commit().map_err(Into::into)
+    }
+
+    fn replace_operation(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+        operation: &Operation,
+    ) -> Result<()> {
+        // Taking the write lock up front keeps other processes from changing the
+        // operation between checking and replacing it
+        let transaction = self
+            .connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let position = position_of(&transaction, month, index, expected)?;
+        let (kind, _) = operation.kind.name_and_symbol();
+
+        transaction.execute(
+            "UPDATE operations
+             SET kind = ?2, day = ?3, amount = ?4, description = ?5, time = ?6,
+                 category = ?7, currency = ?8, id = ?9
+             WHERE position = ?1",
+            params![
+                position,
+                kind,
+                operation.day,
+                operation.amount.to_string(),
+                operation.description,
+                operation.time.map(format_time),
+                operation.category,
+                operation.currency,
+                operation.id,
+            ],
+        )?;
+
+        transaction.commit().map_err(Into::into)
+    }
+
+    fn remove_operation(
+        &mut self,
+        month: &BookkeepingFile,
+        index: usize,
+        expected: &Operation,
+    ) -> Result<()> {
+        let transaction = self
+            .connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let position = position_of(&transaction, month, index, expected)?;
+
+        transaction.execute(
+            "DELETE FROM operations WHERE position = ?1",
+            params![position],
+        )?;
+
+        transaction.commit().map_err(Into::into)
+    }
+
+    fn set_target(&mut self, month: &BookkeepingFile, target: Option<i64>) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO months (year, month, target) VALUES (?1, ?2, ?3)
+             ON CONFLICT (year, month) DO UPDATE SET target = excluded.target",
+            params![month.year(), month.month(), target],
+        )?;
+
+        Ok(())
+    }
+
+    fn list_months(&self) -> Result<Vec<BookkeepingFile>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT year, month FROM months ORDER BY year, month")?;
+
+        let months = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter_map(|(year, month)| BookkeepingFile::from_year_and_month(year, month))
+            .collect();
+
+        Ok(months)
+    }
+
+    fn write_month(&mut self, month: &BookkeepingFile, data: &Month) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        let key = (month.year(), month.month());
+
+        transaction.execute(
+            "INSERT INTO months (year, month, target) VALUES (?1, ?2, ?3)
+             ON CONFLICT (year, month) DO UPDATE SET target = excluded.target",
+            params![key.0, key.1, data.target],
+        )?;
+        transaction.execute(
+            "DELETE FROM operations WHERE year = ?1 AND month = ?2",
+            params![key.0, key.1],
+        )?;
+        for operation in &data.operations {
+            insert_operation(&transaction, month, operation)?;
+        }
+
+        transaction.commit().map_err(Into::into)
+    }
+}
+
+fn insert_operation(
+    connection: &Connection,
+    month: &BookkeepingFile,
+    operation: &Operation,
+) -> Result<()> {
+    let (kind, _) = operation.kind.name_and_symbol();
+
+    connection.execute(
+        "INSERT INTO operations
+         (year, month, kind, day, amount, description, time, category, currency, id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            month.year(),
+            month.month(),
+            kind,
+            operation.day,
+            operation.amount.to_string(),
+            operation.description,
+            operation.time.map(format_time),
+            operation.category,
+            operation.currency,
+            operation.id,
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// The position of the row of `expected`, the operation at `index` of `month`
+fn position_of(
+    connection: &Connection,
+    month: &BookkeepingFile,
+    index: usize,
+    expected: &Operation,
+) -> Result<i64> {
+    let stored = connection
+        .query_row(
+            "SELECT position, kind, day, amount, description, time, category, currency, id
+             FROM operations WHERE year = ?1 AND month = ?2 ORDER BY position
+             LIMIT 1 OFFSET ?3",
+            params![month.year(), month.month(), index],
+            |row| {
+                Ok((
+                    row.get::<_, i64>("position")?,
+                    StoredOperation::from_row(row)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    let (position, operation) = match stored {
+        Some((position, stored)) => (Some(position), Some(stored.into_operation()?)),
+        None => (None, None),
+    };
+    check_stored(month, operation.as_ref(), expected)?;
+
+    // Safety: checked to be stored above
+    Ok(position.unwrap())
+}
+
+/// An operation as it is stored in the database, before being validated
+struct StoredOperation {
+    kind: String,
+    day: i64,
+    amount: String,
+    description: String,
+    time: Option<String>,
+    category: Option<String>,
+    currency: Option<String>,
+    id: Option<String>,
+}
+
+impl StoredOperation {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            kind: row.get("kind")?,
+            day: row.get("day")?,
+            amount: row.get("amount")?,
+            description: row.get("description")?,
+            time: row.get("time")?,
+            category: row.get("category")?,
+            currency: row.get("currency")?,
+            id: row.get("id")?,
+        })
+    }
+
+    fn into_operation(self) -> Result<Operation> {
+        let kind = match self.kind.as_str() {
+            "take" => OperationType::Withdraw,
+            "put" => OperationType::Deposit,
+            other => return Err(ParseError::InvalidOperationType(other.to_owned()).into()),
+        };
+        let day =
+            u8::try_from(self.day).map_err(|_| ParseError::InvalidDay(self.day.to_string()))?;
+        let amount = BigDecimal::from_str(&self.amount)
+            .map_err(|_| ParseError::InvalidDecimal(self.amount.clone()))?;
+        let time = self
+            .time
+            .map(|time| {
+                parse_time(&time).ok_or(ParseError::InvalidField { field: "time", value: time })
+            })
+            .transpose()?;
+
+        let mut operation = Operation::new(day, kind, amount, self.description);
+        operation.time = time;
+        operation.category = self.category;
+        operation.currency = self.currency;
+        operation.id = self.id;
+
+        Ok(operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+
+    use super::SqliteStorage;
+    use crate::{
+        file::BookkeepingFile,
+        parser::{Operation, OperationType},
+        storage::{Access, Month, Storage},
+        Error,
+    };
+
+    fn month(name: &str) -> BookkeepingFile {
+        BookkeepingFile::from_file_name(name).unwrap()
+    }
+
+    #[test]
+    fn stores_months_losslessly() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+
+        let mut lunch = Operation::new(
+            3,
+            OperationType::Withdraw,
+            BigDecimal::from_str("12.50").unwrap(),
+            "Lunch",
+        );
+        lunch.time = chrono::NaiveTime::from_hms_opt(12, 30, 15);
+        lunch.currency = Some("USD".into());
+        let salary = Operation::new(1, OperationType::Deposit, BigDecimal::from(3000), "Salary");
+
+        let october = Month {
+            operations: vec![lunch, salary.clone()],
+            target: Some(1000),
+        };
+        storage.write_month(&month("10-2024"), &october).unwrap();
+        storage
+            .append_operation(&month("01-2024"), &salary)
+            .unwrap();
+
+        assert_eq!(
+            storage
+                .load_month(&month("10-2024"), Access::ReadOnly)
+                .unwrap(),
+            october
+        );
+        assert_eq!(
+            storage.list_months().unwrap(),
+            [month("01-2024"), month("10-2024")]
+        );
+
+        // Writing a month replaces what was there
+        storage
+            .write_month(&month("10-2024"), &Month::default())
+            .unwrap();
+        assert_eq!(
+            storage
+                .load_month(&month("10-2024"), Access::ReadOnly)
+                .unwrap(),
+            Month::default()
+        );
+    }
+
+    #[test]
+    fn edits_operations_in_place() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let october = month("10-2024");
+        let lunch = Operation::new(3, OperationType::Withdraw, BigDecimal::from(12), "Lunch");
+        let salary = Operation::new(1, OperationType::Deposit, BigDecimal::from(3000), "Salary");
+        storage.append_operation(&october, &lunch).unwrap();
+        storage.append_operation(&october, &salary).unwrap();
+
+        let mut dinner = lunch.clone();
+        dinner.description = "Dinner".into();
+        storage
+            .replace_operation(&october, 0, &lunch, &dinner)
+            .unwrap();
+        storage.remove_operation(&october, 1, &salary).unwrap();
+        storage.set_target(&october, Some(800)).unwrap();
+
+        assert!(matches!(
+            storage.remove_operation(&october, 0, &lunch),
+            Err(Error::OperationChanged(_))
+        ));
+        assert!(matches!(
+            storage.remove_operation(&october, 1, &salary),
+            Err(Error::OperationChanged(_))
+        ));
+        assert_eq!(
+            storage.load_month(&october, Access::ReadOnly).unwrap(),
+            Month {
+                operations: vec![dinner],
+                target: Some(800)
+            }
+        );
+    }
+}
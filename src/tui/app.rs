@@ -0,0 +1,649 @@
+use std::{
+    ops::Not,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::{Datelike, Local};
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+use porquinho::{
+    config::Config,
+    file::BookkeepingFile,
+    parser::{Operation, OperationType},
+    storage,
+    vault::Vault,
+    Access, BigDecimal, Bookkeeper, Result,
+};
+
+/// Names of the fields of the form, in the order they're shown
+pub const FIELDS: [&str; 5] = ["Kind", "Amount", "Description", "Day", "Category"];
+
+/// Which side of the screen the arrow keys move in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Months,
+    Operations,
+}
+
+/// What the keys are currently doing
+#[derive(Debug)]
+pub enum Mode {
+    Browsing,
+    /// Typing the text that operations are filtered by
+    Filtering,
+    Editing(Form),
+    ConfirmingDeletion,
+    /// Typing the target of the month, empty for none
+    SettingTarget(String),
+}
+
+/// An operation being added or edited
+#[derive(Debug)]
+pub struct Form {
+    /// Index of the operation being edited among the stored ones, `None` when adding
+    pub index: Option<usize>,
+    pub kind: OperationType,
+    /// Text of the fields after the kind
+    pub texts: [String; 4],
+    /// Index of the field being typed in, in `FIELDS`
+    pub field: usize,
+}
+
+impl Form {
+    fn new(day: u8) -> Self {
+        Self {
+            index: None,
+            kind: OperationType::Withdraw,
+            texts: [String::new(), String::new(), day.to_string(), String::new()],
+            field: 1,
+        }
+    }
+
+    fn editing(index: usize, operation: &Operation) -> Self {
+        Self {
+            index: Some(index),
+            kind: operation.kind.clone(),
+            texts: [
+                operation.amount.to_string(),
+                operation.description.clone(),
+                operation.day.to_string(),
+                operation.category.clone().unwrap_or_default(),
+            ],
+            field: 1,
+        }
+    }
+
+    /// The text typed in `field`, or the kind if it's the first one
+    pub fn text(&self, field: usize) -> &str {
+        match field {
+            0 => self.kind.name_and_symbol().0,
+            _ => &self.texts[field - 1],
+        }
+    }
+
+    /// The operation described by the form, replacing `original` if editing one,
+    /// which keeps the fields the form doesn't show
+    fn operation(
+        &self,
+        month: &BookkeepingFile,
+        config: &Config,
+        original: Option<&Operation>,
+    ) -> Result<Operation, String> {
+        let [amount, description, day, category] = &self.texts;
+
+        let amount = BigDecimal::from_str(amount.trim())
+            .map_err(|_| format!("'{amount}' is not an amount"))?;
+
+        let description = description.trim();
+        if description.is_empty() {
+            return Err("the description is missing".into());
+        }
+
+        let day = day
+            .trim()
+            .parse()
+            .ok()
+            .filter(|day| month.has_day(*day))
+            .ok_or_else(|| format!("'{day}' is not a day of {}", month.as_str()))?;
+
+        let category =
+            Some(category.trim().to_owned()).filter(|category| category.is_empty().not());
+        if let Some(category) = &category {
+            config
+                .check_category(category)
+                .map_err(|err| err.to_string())?;
+        }
+
+        let mut operation = original
+            .cloned()
+            .unwrap_or_else(|| Operation::new(day, self.kind.clone(), amount.clone(), description));
+        operation.day = day;
+        operation.kind = self.kind.clone();
+        operation.amount = amount;
+        operation.description = description.to_owned();
+        operation.category = category;
+
+        Ok(operation)
+    }
+}
+
+/// State of the terminal interface
+pub struct App<'a> {
+    folder: PathBuf,
+    vault: Vault,
+    pub config: &'a Config,
+    /// Months with something stored, along with the current one
+    pub months: Vec<BookkeepingFile>,
+    pub selected_month: usize,
+    /// The month shown, loaded without locking it, which only happens while changing it
+    pub bookkeeper: Bookkeeper,
+    /// Indices of the shown operations among the stored ones, sorted by day
+    pub rows: Vec<usize>,
+    pub selected_row: usize,
+    pub focus: Focus,
+    pub mode: Mode,
+    /// Only operations whose description or category contain it are shown
+    pub filter: String,
+    /// What the last action did, or what went wrong with it
+    pub message: Option<String>,
+    /// A description of every change made to the books, e.g. `delete take 12 Lunch (10-2026)`
+    pub changes: Vec<String>,
+    running: bool,
+}
+
+impl<'a> App<'a> {
+    pub fn new(folder: &Path, vault: Vault, config: &'a Config) -> Result<Self> {
+        let current = BookkeepingFile::current_file();
+        let storage = storage::open(folder, vault.clone())?;
+        let months = months_with(storage.list_months()?, current);
+        let bookkeeper = Bookkeeper::load(storage, current, Access::ReadOnly)?;
+
+        let mut app = Self {
+            folder: folder.to_owned(),
+            vault,
+            config,
+            selected_month: months
+                .iter()
+                .position(|month| *month == current)
+                .unwrap_or(0),
+            months,
+            bookkeeper,
+            rows: vec![],
+            selected_row: 0,
+            focus: Focus::Operations,
+            mode: Mode::Browsing,
+            filter: String::new(),
+            message: None,
+            changes: vec![],
+            running: true,
+        };
+        app.update_rows();
+
+        Ok(app)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn month(&self) -> BookkeepingFile {
+        self.months[self.selected_month]
+    }
+
+    /// The operation under the cursor, along with its index among the stored ones
+    pub fn selected_operation(&self) -> Option<(usize, &Operation)> {
+        let index = *self.rows.get(self.selected_row)?;
+
+        Some((index, &self.bookkeeper.status().all_operations[index]))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match std::mem::replace(&mut self.mode, Mode::Browsing) {
+            Mode::Browsing => self.browse(key.code),
+            Mode::Filtering => self.type_filter(key.code),
+            Mode::Editing(form) => self.edit(form, key.code),
+            Mode::ConfirmingDeletion => self.confirm_deletion(key.code),
+            Mode::SettingTarget(text) => self.type_target(text, key.code),
+        }
+    }
+
+    fn browse(&mut self, code: KeyCode) {
+        self.message = None;
+
+        match code {
+            KeyCode::Char('q') => self.running = false,
+            KeyCode::Esc if self.filter.is_empty() => self.running = false,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.update_rows();
+            }
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Left | KeyCode::Right => {
+                self.focus = match self.focus {
+                    Focus::Months => Focus::Operations,
+                    Focus::Operations => Focus::Months,
+                };
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::Char('a') => {
+                let today = Local::today();
+                let day = if self.month() == BookkeepingFile::current_file() {
+                    today.day() as u8
+                } else {
+                    1
+                };
+                self.mode = Mode::Editing(Form::new(day));
+            }
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some((index, operation)) = self.selected_operation() {
+                    self.mode = Mode::Editing(Form::editing(index, operation));
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete if self.selected_operation().is_some() => {
+                self.mode = Mode::ConfirmingDeletion;
+            }
+            KeyCode::Char('/') => self.mode = Mode::Filtering,
+            KeyCode::Char('t') => {
+                let target = self.bookkeeper.target().map(|target| target.to_string());
+                self.mode = Mode::SettingTarget(target.unwrap_or_default());
+            }
+            _ => {}
+        }
+    }
+
+    fn move_cursor(&mut self, step: isize) {
+        match self.focus {
+            Focus::Months => {
+                let selected = step_within(self.selected_month, step, self.months.len());
+                if selected != self.selected_month {
+                    self.selected_month = selected;
+                    self.selected_row = 0;
+                    let result = self.reload();
+                    self.report(result.map(|_| None));
+                }
+            }
+            Focus::Operations => {
+                self.selected_row = step_within(self.selected_row, step, self.rows.len());
+            }
+        }
+    }
+
+    fn type_filter(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Enter => return,
+            KeyCode::Esc => self.filter.clear(),
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            KeyCode::Char(char) => self.filter.push(char),
+            _ => {}
+        }
+
+        if code != KeyCode::Esc {
+            self.mode = Mode::Filtering;
+        }
+        self.selected_row = 0;
+        self.update_rows();
+    }
+
+    fn edit(&mut self, mut form: Form, code: KeyCode) {
+        match code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => {
+                let original = form.index.map(|index| {
+                    let operation = &self.bookkeeper.status().all_operations[index];
+                    (index, operation.clone())
+                });
+
+                match form.operation(
+                    &self.month(),
+                    self.config,
+                    original.as_ref().map(|(_, operation)| operation),
+                ) {
+                    Ok(operation) => {
+                        let result = self.save(original, operation);
+                        self.report(result.map(Some));
+                        return;
+                    }
+                    Err(message) => self.message = Some(message),
+                }
+            }
+            KeyCode::Tab | KeyCode::Down => form.field = (form.field + 1) % FIELDS.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                form.field = (form.field + FIELDS.len() - 1) % FIELDS.len();
+            }
+            // The kind is toggled rather than typed
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if form.field == 0 => {
+                form.kind = match form.kind {
+                    OperationType::Withdraw => OperationType::Deposit,
+                    OperationType::Deposit => OperationType::Withdraw,
+                };
+            }
+            KeyCode::Backspace if form.field > 0 => {
+                form.texts[form.field - 1].pop();
+            }
+            KeyCode::Char(char) if form.field > 0 => form.texts[form.field - 1].push(char),
+            _ => {}
+        }
+
+        self.mode = Mode::Editing(form);
+    }
+
+    /// Adds `operation`, or puts it in place of `original`, along with its index among
+    /// the stored ones, which fails if another process moved it since it was shown
+    fn save(
+        &mut self,
+        original: Option<(usize, Operation)>,
+        operation: Operation,
+    ) -> Result<String> {
+        let description = operation.description.clone();
+        let (verb, summary) = match &original {
+            Some((_, original)) => (
+                "Changed",
+                format!("edit {} into {}", original.summary(), operation.summary()),
+            ),
+            None => ("Added", operation.summary()),
+        };
+
+        self.change(summary, |bookkeeper| match &original {
+            Some((index, original)) => bookkeeper.replace_operation(*index, original, operation),
+            None => bookkeeper.add_operation(operation),
+        })?;
+
+        Ok(format!("{verb} \"{description}\""))
+    }
+
+    fn confirm_deletion(&mut self, code: KeyCode) {
+        let (index, operation) = match (code, self.selected_operation()) {
+            (KeyCode::Char('y'), Some((index, operation))) => (index, operation.clone()),
+            _ => return,
+        };
+
+        let result = self
+            .change(format!("delete {}", operation.summary()), |bookkeeper| {
+                bookkeeper.remove_operation(index, &operation)
+            })
+            .map(|_| Some("Deleted the operation".to_owned()));
+        self.report(result);
+    }
+
+    fn type_target(&mut self, mut text: String, code: KeyCode) {
+        match code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => {
+                let target = match text.trim() {
+                    "" => None,
+                    number => match number.parse() {
+                        Ok(target) => Some(target),
+                        Err(_) => {
+                            self.message = Some(format!("'{number}' is not a whole amount"));
+                            self.mode = Mode::SettingTarget(text);
+                            return;
+                        }
+                    },
+                };
+
+                let summary = match target {
+                    Some(target) => format!("target {target}"),
+                    None => "remove the target".to_owned(),
+                };
+                let result = self
+                    .change(summary, |bookkeeper| bookkeeper.set_target(target))
+                    .map(|_| Some("Changed the target".to_owned()));
+                self.report(result);
+                return;
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(char) if char.is_ascii_digit() => text.push(char),
+            _ => {}
+        }
+
+        self.mode = Mode::SettingTarget(text);
+    }
+
+    /// Changes the month shown, which is locked against other porquinho processes meanwhile,
+    /// keeping `summary` among the changes made if it worked
+    fn change(
+        &mut self,
+        summary: String,
+        change: impl FnOnce(&mut Bookkeeper) -> Result<()>,
+    ) -> Result<()> {
+        let month = self.month();
+        let storage = storage::open(&self.folder, self.vault.clone())?;
+        let mut bookkeeper = Bookkeeper::load(storage, month, Access::ReadWrite)?;
+        let changed = change(&mut bookkeeper);
+        if changed.is_ok() {
+            self.changes.push(format!("{summary} ({})", month.as_str()));
+        }
+
+        // Dropping it releases the lock
+        drop(bookkeeper);
+        // Shown as stored even if the change failed, e.g. because another process
+        // changed the month meanwhile
+        self.reload()?;
+
+        changed
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let month = self.month();
+        let storage = storage::open(&self.folder, self.vault.clone())?;
+
+        self.months = months_with(storage.list_months()?, month);
+        self.selected_month = self
+            .months
+            .iter()
+            .position(|other| *other == month)
+            .unwrap_or(0);
+        self.bookkeeper = Bookkeeper::load(storage, month, Access::ReadOnly)?;
+        self.update_rows();
+
+        Ok(())
+    }
+
+    fn update_rows(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let operations = &self.bookkeeper.status().all_operations;
+
+        let mut rows: Vec<_> = (0..operations.len())
+            .filter(|index| {
+                let operation = &operations[*index];
+                let category = operation.category.as_deref().unwrap_or_default();
+
+                operation.description.to_lowercase().contains(&filter)
+                    || category.to_lowercase().contains(&filter)
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            let (a, b) = (&operations[*a], &operations[*b]);
+            a.day.cmp(&b.day).then(a.kind.cmp(&b.kind))
+        });
+
+        self.rows = rows;
+        self.selected_row = self.selected_row.min(self.rows.len().saturating_sub(1));
+    }
+
+    fn report(&mut self, result: Result<Option<String>>) {
+        match result {
+            Ok(Some(message)) => self.message = Some(message),
+            Ok(None) => {}
+            Err(err) => self.message = Some(format!("Error: {err}")),
+        }
+    }
+}
+
+/// `months`, sorted, with `month` among them
+fn months_with(mut months: Vec<BookkeepingFile>, month: BookkeepingFile) -> Vec<BookkeepingFile> {
+    if months.contains(&month).not() {
+        months.push(month);
+        months.sort_by_key(|month| (month.year(), month.month()));
+    }
+
+    months
+}
+
+/// `index` moved by `step`, kept within a list of `len` items
+fn step_within(index: usize, step: isize, len: usize) -> usize {
+    index.saturating_add_signed(step).min(len.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Not, str::FromStr};
+
+    use fs_err as fs;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use porquinho::{
+        config::Config, file::BookkeepingFile, storage, vault::Vault, Access, BigDecimal,
+        Bookkeeper,
+    };
+
+    use super::{App, Mode};
+
+    fn press(app: &mut App, keys: &str) {
+        for char in keys.chars() {
+            let code = match char {
+                '\n' => KeyCode::Enter,
+                '\t' => KeyCode::Tab,
+                '\x08' => KeyCode::Backspace,
+                '\x1b' => KeyCode::Esc,
+                char => KeyCode::Char(char),
+            };
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    fn descriptions(app: &App) -> Vec<String> {
+        let operations = &app.bookkeeper.status().all_operations;
+
+        app.rows
+            .iter()
+            .map(|index| operations[*index].description.clone())
+            .collect()
+    }
+
+    #[test]
+    fn adds_edits_and_deletes_operations() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        let mut app = App::new(dir.path(), Vault::default(), &config).unwrap();
+
+        // The form starts at the amount, then goes through description, day and category
+        press(&mut app, "a12.50\tLunch\t\x08\x081\tfood\n");
+        // Tabbing past the category gets to the kind, which space toggles
+        press(&mut app, "a900\tSalary\t\x08\x082\t\t \n");
+        assert_eq!(app.message.as_deref(), Some("Added \"Salary\""));
+        assert_eq!(descriptions(&app), ["Lunch", "Salary"]);
+        assert_eq!(
+            app.bookkeeper.status().take_total,
+            BigDecimal::from_str("12.50").unwrap()
+        );
+
+        let month = BookkeepingFile::current_file();
+        let contents = fs::read_to_string(dir.path().join(month.as_path())).unwrap();
+        assert!(contents.contains("description = \"Lunch\"\ncategory = \"food\""));
+
+        // Editing keeps the fields already typed
+        press(&mut app, "e\t\x08\x08\x08\x08\x08Dinner\n");
+        assert_eq!(descriptions(&app), ["Dinner", "Salary"]);
+        assert_eq!(
+            app.bookkeeper.status().all_operations[0]
+                .category
+                .as_deref(),
+            Some("food")
+        );
+
+        // Nothing is deleted unless confirmed
+        press(&mut app, "jdn");
+        assert_eq!(descriptions(&app), ["Dinner", "Salary"]);
+        press(&mut app, "dy");
+        assert_eq!(descriptions(&app), ["Dinner"]);
+
+        press(&mut app, "t500\n");
+        assert_eq!(app.bookkeeper.target(), Some(500));
+
+        let month = month.as_str();
+        assert_eq!(
+            app.changes,
+            [
+                format!("take 12.50 Lunch ({month})"),
+                format!("put 900 Salary ({month})"),
+                format!("edit take 12.50 Lunch into take 12.50 Dinner ({month})"),
+                format!("delete put 900 Salary ({month})"),
+                format!("target 500 ({month})"),
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_to_delete_operations_moved_by_another_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        let mut app = App::new(dir.path(), Vault::default(), &config).unwrap();
+        press(&mut app, "a12\tLunch\n");
+        press(&mut app, "a30\tUber\n");
+
+        // Another process removes the lunch while the app still shows it
+        let storage = storage::open(dir.path(), Vault::default()).unwrap();
+        let mut other = Bookkeeper::load(storage, app.month(), Access::ReadWrite).unwrap();
+        let lunch = other.status().all_operations[0].clone();
+        other.remove_operation(0, &lunch).unwrap();
+        drop(other);
+
+        press(&mut app, "kkdy");
+        let message = app.message.clone().unwrap();
+        assert!(message.starts_with("Error: The operations of"), "{message}");
+        // Only what was stored is described
+        assert_eq!(app.changes.len(), 2);
+        assert_eq!(descriptions(&app), ["Uber"]);
+    }
+
+    #[test]
+    fn keeps_the_form_open_until_it_is_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            categories: vec!["food".into()],
+            ..Config::default()
+        };
+        let mut app = App::new(dir.path(), Vault::default(), &config).unwrap();
+
+        press(&mut app, "alots\tLunch\n");
+        assert_eq!(app.message.as_deref(), Some("'lots' is not an amount"));
+        assert!(matches!(app.mode, Mode::Editing(_)));
+
+        press(&mut app, "\t\t\t\t\x08\x08\x08\x0812\t\t\tfun\n");
+        assert!(matches!(app.mode, Mode::Editing(_)));
+
+        press(&mut app, "\x1b");
+        assert!(matches!(app.mode, Mode::Browsing));
+        assert!(app.rows.is_empty());
+    }
+
+    #[test]
+    fn filters_by_description_and_category() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        let mut app = App::new(dir.path(), Vault::default(), &config).unwrap();
+
+        press(&mut app, "a12\tLunch\t\tfood\n");
+        press(&mut app, "a30\tGroceries\t\tFOOD\n");
+        press(&mut app, "a5\tBus\n");
+
+        press(&mut app, "/lunch\n");
+        assert_eq!(descriptions(&app), ["Lunch"]);
+
+        press(&mut app, "/\x08\x08\x08\x08\x08food");
+        assert_eq!(descriptions(&app).len(), 2);
+
+        // Esc clears the filter before quitting
+        press(&mut app, "\n\x1b");
+        assert_eq!(descriptions(&app).len(), 3);
+        assert!(app.is_running());
+        press(&mut app, "\x1b");
+        assert!(app.is_running().not());
+    }
+}
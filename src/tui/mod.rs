@@ -0,0 +1,49 @@
+mod app;
+mod ui;
+
+use std::path::Path;
+
+use log::LevelFilter;
+use ratatui::{
+    crossterm::event::{self, Event, KeyEventKind},
+    DefaultTerminal,
+};
+
+use porquinho::{config::Config, vault::Vault, Result};
+
+use self::app::App;
+
+/// Shows the books in `folder` in a full-screen interface until it's quit, adding
+/// a description of every change made to the books to `changes`
+pub fn run(folder: &Path, config: &Config, changes: &mut Vec<String>) -> Result<()> {
+    let mut app = App::new(folder, Vault::unlock(folder)?, config)?;
+
+    // Messages would be drawn over the interface, which shows what changed anyway
+    let level = log::max_level();
+    log::set_max_level(LevelFilter::Off);
+
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+
+    log::set_max_level(level);
+    // Even if the interface failed, what was changed until then is kept
+    changes.append(&mut app.changes);
+
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    while app.is_running() {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        if let Event::Key(key) = event::read()? {
+            // Windows reports releases as well
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(key);
+            }
+        }
+    }
+
+    Ok(())
+}
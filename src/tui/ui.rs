@@ -0,0 +1,257 @@
+use std::ops::Not;
+
+use bigdecimal::{ToPrimitive, Zero};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{
+        Block, BorderType, Borders, Cell, Clear, Gauge, List, ListState, Paragraph, Row, Table,
+        TableState,
+    },
+    Frame,
+};
+
+use porquinho::{config::Config, BigDecimal};
+
+use super::app::{App, Focus, Form, Mode, FIELDS};
+
+/// From how much of the target on the budget bar turns yellow
+const WARNING_RATIO: f64 = 0.8;
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [header, budget, body, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Min(5),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [months, operations] =
+        Layout::horizontal([Constraint::Length(13), Constraint::Min(20)]).areas(body);
+
+    draw_header(frame, app, header);
+    draw_budget(frame, app, budget);
+    draw_months(frame, app, months);
+    draw_operations(frame, app, operations);
+    draw_footer(frame, app, footer);
+
+    if let Mode::Editing(form) = &app.mode {
+        draw_form(frame, app, form);
+    }
+}
+
+fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+    let status = app.bookkeeper.status();
+    let balance = &status.put_total - &status.take_total;
+    let balance_color = if balance < BigDecimal::zero() {
+        Color::Red
+    } else {
+        Color::Green
+    };
+
+    let line = Line::from(vec![
+        Span::raw("Incoming "),
+        Span::raw(money(app.config, &status.put_total)).bold(),
+        Span::raw("   Outgoing "),
+        Span::raw(money(app.config, &status.take_total)).bold(),
+        Span::raw("   Balance "),
+        Span::styled(
+            money(app.config, &balance),
+            Style::new().fg(balance_color).bold(),
+        ),
+    ]);
+
+    let title = format!(" porquinho · {} ", app.month().as_str());
+    frame.render_widget(Paragraph::new(line).block(block(app.config, title)), area);
+}
+
+fn draw_budget(frame: &mut Frame, app: &App, area: Rect) {
+    let block = block(app.config, " Budget ");
+    let take_total = &app.bookkeeper.status().take_total;
+
+    let target = match app.bookkeeper.target() {
+        Some(target) if target > 0 => target,
+        _ => {
+            let text = Paragraph::new("No target for this month, press t to set one")
+                .dim()
+                .block(block);
+            frame.render_widget(text, area);
+            return;
+        }
+    };
+
+    let ratio = take_total.to_f64().unwrap_or_default() / target as f64;
+    let color = if ratio > 1.0 {
+        Color::Red
+    } else if ratio >= WARNING_RATIO {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+
+    let label = format!(
+        "{} of {} ({:.0}%)",
+        money(app.config, take_total),
+        money(app.config, &BigDecimal::from(target)),
+        ratio * 100.0
+    );
+    let gauge = Gauge::default()
+        .block(block)
+        .gauge_style(Style::new().fg(color))
+        .ratio(ratio.clamp(0.0, 1.0))
+        .label(label);
+
+    frame.render_widget(gauge, area);
+}
+
+fn draw_months(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<_> = app.months.iter().map(|month| month.as_str()).collect();
+
+    let list = List::new(items)
+        .block(focused(
+            block(app.config, " Months "),
+            app.focus == Focus::Months,
+        ))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.selected_month));
+
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_operations(frame: &mut Frame, app: &App, area: Rect) {
+    let operations = &app.bookkeeper.status().all_operations;
+    let decimals = app.config.decimals;
+
+    let rows = app.rows.iter().map(|index| {
+        let operation = &operations[*index];
+        let (kind, _) = operation.kind.name_and_symbol();
+
+        Row::new([
+            Cell::from(format!("{:2}", operation.day)),
+            Cell::from(kind),
+            Cell::from(format!("{:>10.decimals$}", operation.amount)),
+            Cell::from(operation.description.as_str()),
+            Cell::from(operation.category.as_deref().unwrap_or_default()),
+        ])
+    });
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Length(4),
+        Constraint::Length(10),
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+    ];
+
+    let title = if app.filter.is_empty() {
+        " Operations ".to_owned()
+    } else {
+        format!(" Operations matching \"{}\" ", app.filter)
+    };
+    let header = Row::new(["day", "op", "amount", "description", "category"]).bold();
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(focused(
+            block(app.config, title),
+            app.focus == Focus::Operations,
+        ))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let selected = app.rows.is_empty().not().then_some(app.selected_row);
+    let mut state = TableState::default().with_selected(selected);
+
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let line = match &app.mode {
+        Mode::Filtering => Line::from(format!("Filter: {}▏", app.filter)),
+        Mode::ConfirmingDeletion => Line::from("Delete this operation? y to confirm").yellow(),
+        Mode::SettingTarget(text) => Line::from(format!(
+            "Target for {}, empty for none: {text}▏",
+            app.month().as_str()
+        )),
+        Mode::Editing(_) => Line::from(
+            "tab/↓ next field  shift+tab/↑ previous  space switches kind  enter saves  esc cancels",
+        ),
+        Mode::Browsing => match &app.message {
+            Some(message) => Line::from(message.as_str()),
+            None => {
+                Line::from("a add  e edit  d delete  / filter  t target  tab switch pane  q quit")
+                    .dim()
+            }
+        },
+    };
+
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+fn draw_form(frame: &mut Frame, app: &App, form: &Form) {
+    let area = centered(frame.area(), 50, FIELDS.len() as u16 + 3);
+
+    let mut lines: Vec<_> = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(field, name)| {
+            let mut value = Span::raw(form.text(field).to_owned());
+            if field == form.field {
+                value = value.reversed();
+            }
+
+            Line::from(vec![Span::raw(format!("{name:>12}: ")), value])
+        })
+        .collect();
+    if let Some(message) = &app.message {
+        lines.push(Line::from(message.as_str()).red());
+    }
+
+    let title = if form.index.is_some() {
+        " Edit operation "
+    } else {
+        " Add operation "
+    };
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block(app.config, title)), area);
+}
+
+/// A bordered block, with borders like the ones of the tables of the configured theme
+fn block<'a>(config: &Config, title: impl Into<Line<'a>>) -> Block<'a> {
+    let border_type = match config.theme.as_str() {
+        "none" => return Block::new().borders(Borders::NONE).title(title),
+        "rounded" => BorderType::Rounded,
+        "heavy" | "reinforced" => BorderType::Thick,
+        "compact_double" => BorderType::Double,
+        _ => BorderType::Plain,
+    };
+
+    Block::bordered().border_type(border_type).title(title)
+}
+
+fn focused(block: Block, is_focused: bool) -> Block {
+    if is_focused {
+        block.border_style(Style::new().fg(Color::Cyan))
+    } else {
+        block
+    }
+}
+
+fn money(config: &Config, amount: &BigDecimal) -> String {
+    format!(
+        "{} {:.decimals$}",
+        config.currency,
+        amount,
+        decimals = config.decimals
+    )
+}
+
+/// A rectangle of `width` by `height` in the middle of `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+
+    area
+}
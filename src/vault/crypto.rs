@@ -0,0 +1,63 @@
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Nonce,
+};
+
+pub use chacha20poly1305::Key;
+
+use super::SALT_LEN;
+use crate::Result;
+
+const NONCE_LEN: usize = 12;
+
+/// Derives the key of the books from `passphrase` with Argon2id
+pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+
+    // Safety: the default parameters accept salts and keys of these lengths
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut_slice())
+        .unwrap();
+
+    Ok(key)
+}
+
+pub fn random_salt() -> Result<[u8; SALT_LEN]> {
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    Ok(salt)
+}
+
+/// Encrypts and authenticates `plaintext` with ChaCha20-Poly1305
+///
+/// Every call uses a new random nonce, which is kept before the ciphertext.
+pub fn seal(key: &Key, plaintext: &[u8]) -> Vec<u8> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    // Safety: only fails for plaintexts of hundreds of gigabytes
+    let ciphertext = ChaCha20Poly1305::new(key)
+        .encrypt(&nonce, plaintext)
+        .unwrap();
+
+    [nonce.as_slice(), &ciphertext].concat()
+}
+
+/// The plaintext `sealed` by [`seal`], if it was sealed with `key` and wasn't
+/// changed since
+pub fn unseal(key: &Key, sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+    ChaCha20Poly1305::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .ok()
+}
+
+pub fn prompt_passphrase(prompt: &str) -> Result<String> {
+    rpassword::prompt_password(prompt).map_err(Into::into)
+}
@@ -0,0 +1,379 @@
+#[cfg(feature = "encryption")]
+mod crypto;
+
+use std::{
+    env,
+    ops::Not,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+
+use crate::{
+    file::{replace_atomically, write_atomically, BookkeepingFile},
+    lock::{FileLock, LOCK_TIMEOUT},
+    storage::Backend,
+    Error, Result,
+};
+
+/// Name of the file that marks the books in a folder as encrypted
+///
+/// It keeps the salt their key is derived with, along with a value sealed with
+/// that key, which tells whether a passphrase is the right one.
+pub const KEY_FILE_NAME: &str = "porquinho.key";
+
+/// Read for the passphrase before asking for it, so that porquinho can be scripted
+const PASSPHRASE_VARIABLE: &str = "PORQUINHO_PASSPHRASE";
+
+/// Starts every encrypted file, telling them apart from plain TOML ones
+const SEALED_MAGIC: &[u8] = b"porquinho sealed v1\n";
+const KEY_FILE_MAGIC: &[u8] = b"porquinho key v1\n";
+/// Sealed in the key file to check passphrases against
+const KEY_CHECK: &[u8] = b"porquinho";
+
+const SALT_LEN: usize = 16;
+
+/// Reads and writes bookkeeping files, encrypting them if the books are encrypted
+///
+/// Files that aren't encrypted are always read as they are, so that the books
+/// remain usable if `porquinho encrypt` is interrupted midway.
+#[derive(Clone, Default)]
+pub struct Vault {
+    key: Option<crypto::Key>,
+}
+
+impl Vault {
+    /// The vault of the books in `folder`, asking for their passphrase if they're encrypted
+    pub fn unlock(folder: &Path) -> Result<Self> {
+        if is_encrypted(folder) {
+            Self::unlock_with(folder, &read_passphrase()?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    fn unlock_with(folder: &Path, passphrase: &str) -> Result<Self> {
+        let key_path = folder.join(KEY_FILE_NAME);
+        let contents = fs::read(&key_path)?;
+
+        let (salt, check) = match contents.strip_prefix(KEY_FILE_MAGIC) {
+            Some(rest) if rest.len() > SALT_LEN => rest.split_at(SALT_LEN),
+            _ => return Err(Error::Undecryptable(key_path)),
+        };
+
+        let key = crypto::derive_key(passphrase, salt)?;
+        if crypto::unseal(&key, check).as_deref() != Some(KEY_CHECK) {
+            return Err(Error::WrongPassphrase);
+        }
+
+        Ok(Self { key: Some(key) })
+    }
+
+    /// Starts encrypting the books in `folder` with a key derived from `passphrase`
+    fn create(folder: &Path, passphrase: &str) -> Result<Self> {
+        let salt = crypto::random_salt()?;
+        let key = crypto::derive_key(passphrase, &salt)?;
+
+        let contents = [KEY_FILE_MAGIC, &salt, &crypto::seal(&key, KEY_CHECK)].concat();
+        replace_atomically(&folder.join(KEY_FILE_NAME), &contents)?;
+
+        Ok(Self { key: Some(key) })
+    }
+
+    /// Whether the files written through this vault are encrypted
+    pub fn is_encrypting(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Reads the bookkeeping file at `path`, decrypting it if it's encrypted
+    pub fn read_to_string(&self, path: &Path) -> Result<String> {
+        let mut contents = fs::read(path)?;
+
+        if contents.starts_with(SEALED_MAGIC) {
+            let key = self
+                .key
+                .as_ref()
+                .ok_or_else(|| Error::MissingKey(path.to_owned()))?;
+
+            contents = crypto::unseal(key, &contents[SEALED_MAGIC.len()..])
+                .ok_or_else(|| Error::Undecryptable(path.to_owned()))?;
+        }
+
+        String::from_utf8(contents).map_err(|err| err.utf8_error().into())
+    }
+
+    /// Writes `contents` to the file at `path`
+    pub fn write(&self, path: &Path, contents: &str) -> Result<()> {
+        fs::write(path, self.encode(contents)).map_err(Into::into)
+    }
+
+    /// Replaces the contents of the file at `path`, see [`write_atomically`]
+    pub fn write_atomically(&self, path: &Path, contents: &str) -> Result<()> {
+        write_atomically(path, &self.encode(contents))
+    }
+
+    fn encode(&self, contents: &str) -> Vec<u8> {
+        match &self.key {
+            Some(key) => [SEALED_MAGIC, &crypto::seal(key, contents.as_bytes())].concat(),
+            None => contents.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Whether the books in `folder` are encrypted
+pub fn is_encrypted(folder: &Path) -> bool {
+    folder.join(KEY_FILE_NAME).exists()
+}
+
+/// Encrypts the books in `folder`, returning how many files were encrypted
+///
+/// Books that are already encrypted keep their passphrase, and only the files
+/// left unencrypted, e.g. by an interrupted run, are encrypted.
+pub fn encrypt_books(folder: &Path) -> Result<usize> {
+    if Backend::in_use(folder) == Backend::Sqlite {
+        return Err(Error::EncryptedSqlite);
+    }
+
+    let vault = if is_encrypted(folder) {
+        Vault::unlock(folder)?
+    } else {
+        Vault::create(folder, &read_new_passphrase()?)?
+    };
+
+    convert_files(folder, &vault, &vault)
+}
+
+/// Decrypts the books in `folder` for good, returning how many files were decrypted
+pub fn decrypt_books(folder: &Path) -> Result<usize> {
+    if is_encrypted(folder).not() {
+        return Err(Error::NotEncrypted(folder.to_owned()));
+    }
+
+    let vault = Vault::unlock(folder)?;
+    let decrypted = convert_files(folder, &vault, &Vault::default())?;
+
+    // Removed last, so that an interrupted run can be resumed
+    fs::remove_file(folder.join(KEY_FILE_NAME))?;
+
+    Ok(decrypted)
+}
+
+/// Rewrites every file with bookkeeping data in `folder` that isn't encrypted
+/// the way `to` encrypts, returning how many were rewritten
+///
+/// Backups are rewritten as well, instead of keeping a plaintext copy of each file.
+fn convert_files(folder: &Path, from: &Vault, to: &Vault) -> Result<usize> {
+    let mut converted = 0;
+
+    for (path, month) in data_files(folder)? {
+        let _lock = FileLock::acquire(&folder.join(month.as_path()), LOCK_TIMEOUT)?;
+
+        let is_sealed = fs::read(&path)?.starts_with(SEALED_MAGIC);
+        if is_sealed == to.is_encrypting() {
+            continue;
+        }
+
+        let contents = from.read_to_string(&path)?;
+        replace_atomically(&path, &to.encode(&contents))?;
+        converted += 1;
+    }
+
+    Ok(converted)
+}
+
+/// Every file in `folder` with bookkeeping data, along with its month: the month
+/// files, their backups and what's left of them after `porquinho migrate`
+fn data_files(folder: &Path) -> Result<Vec<(PathBuf, BookkeepingFile)>> {
+    let mut files = vec![];
+
+    for entry in fs::read_dir(folder)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let month = file_name.to_str().and_then(month_of_data_file);
+
+        if let Some(month) = month {
+            if entry.file_type()?.is_file() {
+                files.push((entry.path(), month));
+            }
+        }
+    }
+
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(files)
+}
+
+/// The month whose data is kept in the file named `file_name`, e.g. `10-2026.v1.bak`
+fn month_of_data_file(file_name: &str) -> Option<BookkeepingFile> {
+    let month = BookkeepingFile::from_file_name(file_name.get(..7)?)?;
+    let extensions = &file_name[7..];
+
+    let is_data = extensions.is_empty()
+        || (extensions.starts_with('.')
+            && extensions.ends_with(".lock").not()
+            && extensions.ends_with(".tmp").not());
+
+    is_data.then_some(month)
+}
+
+fn read_passphrase() -> Result<String> {
+    match env::var(PASSPHRASE_VARIABLE) {
+        Ok(passphrase) => Ok(passphrase),
+        Err(_) => crypto::prompt_passphrase("Passphrase: "),
+    }
+}
+
+/// The passphrase to encrypt the books with, asked twice to rule out typos
+fn read_new_passphrase() -> Result<String> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_VARIABLE) {
+        return Ok(passphrase);
+    }
+
+    let passphrase = crypto::prompt_passphrase("New passphrase: ")?;
+    if crypto::prompt_passphrase("Repeat the passphrase: ")? != passphrase {
+        return Err(Error::PassphraseMismatch);
+    }
+
+    Ok(passphrase)
+}
+
+/// Stands in for the cryptography when porquinho is built without the `encryption`
+/// feature, which makes encrypted books impossible to open
+#[cfg(not(feature = "encryption"))]
+mod crypto {
+    use std::convert::Infallible;
+
+    use super::SALT_LEN;
+    use crate::{Error, Result};
+
+    /// Never created, since no key can be derived
+    pub type Key = Infallible;
+
+    pub fn derive_key(_passphrase: &str, _salt: &[u8]) -> Result<Key> {
+        Err(Error::EncryptionUnsupported)
+    }
+
+    pub fn random_salt() -> Result<[u8; SALT_LEN]> {
+        Err(Error::EncryptionUnsupported)
+    }
+
+    pub fn seal(key: &Key, _plaintext: &[u8]) -> Vec<u8> {
+        match *key {}
+    }
+
+    pub fn unseal(key: &Key, _sealed: &[u8]) -> Option<Vec<u8>> {
+        match *key {}
+    }
+
+    pub fn prompt_passphrase(_prompt: &str) -> Result<String> {
+        Err(Error::EncryptionUnsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fs_err as fs;
+
+    use super::{month_of_data_file, Vault, SEALED_MAGIC};
+    use crate::Error;
+
+    #[test]
+    fn tells_data_files_apart() {
+        assert!(month_of_data_file("10-2026").is_some());
+        assert!(month_of_data_file("10-2026.v1.bak").is_some());
+        assert!(month_of_data_file("10-2026.migrated").is_some());
+        assert!(month_of_data_file("10-2026.lock").is_none());
+        assert!(month_of_data_file("10-2026.tmp").is_none());
+        assert!(month_of_data_file("10-2026-notes").is_none());
+        assert!(month_of_data_file("porquinho.key").is_none());
+    }
+
+    #[test]
+    fn refuses_to_read_encrypted_files_without_a_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("10-2026");
+
+        fs::write(&path, "format_version = 2\n").unwrap();
+        assert_eq!(
+            Vault::default().read_to_string(&path).unwrap(),
+            "format_version = 2\n"
+        );
+
+        fs::write(&path, [SEALED_MAGIC, b"\x00\x01"].concat()).unwrap();
+        assert!(matches!(
+            Vault::default().read_to_string(&path),
+            Err(Error::MissingKey(_))
+        ));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypts_and_decrypts_the_books() {
+        use std::{ops::Not, str::FromStr};
+
+        use bigdecimal::BigDecimal;
+
+        use super::{convert_files, KEY_FILE_NAME};
+        use crate::{
+            file::BookkeepingFile,
+            parser::{Operation, OperationType},
+            storage::{Access, Storage, TomlDirectory},
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let contents = "format_version = 2\n\n[[take]]\nday = 3\namount = \"12.50\"\ndescription = \"Lunch\"\n";
+        fs::write(dir.path().join("10-2026"), contents).unwrap();
+        fs::write(dir.path().join("10-2026.bak"), "format_version = 2\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "Lunch").unwrap();
+
+        let vault = Vault::create(dir.path(), "correct horse").unwrap();
+        assert_eq!(convert_files(dir.path(), &vault, &vault).unwrap(), 2);
+        // Nothing is left to encrypt
+        assert_eq!(convert_files(dir.path(), &vault, &vault).unwrap(), 0);
+
+        let sealed = fs::read(dir.path().join("10-2026")).unwrap();
+        assert!(sealed.starts_with(SEALED_MAGIC));
+        assert!(String::from_utf8_lossy(&sealed).contains("Lunch").not());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+            "Lunch"
+        );
+
+        assert!(matches!(
+            Vault::unlock_with(dir.path(), "wrong horse"),
+            Err(Error::WrongPassphrase)
+        ));
+        let vault = Vault::unlock_with(dir.path(), "correct horse").unwrap();
+
+        let october = BookkeepingFile::from_file_name("10-2026").unwrap();
+        let mut storage = TomlDirectory::new(dir.path(), vault.clone());
+        let month = storage.load_month(&october, Access::ReadWrite).unwrap();
+        assert_eq!(month.operations[0].description, "Lunch");
+
+        let salary = Operation::new(
+            1,
+            OperationType::Deposit,
+            BigDecimal::from_str("3000").unwrap(),
+            "Salary",
+        );
+        storage.append_operation(&october, &salary).unwrap();
+        drop(storage);
+        assert!(fs::read(dir.path().join("10-2026"))
+            .unwrap()
+            .starts_with(SEALED_MAGIC));
+
+        assert_eq!(
+            convert_files(dir.path(), &vault, &Vault::default()).unwrap(),
+            2
+        );
+        fs::remove_file(dir.path().join(KEY_FILE_NAME)).unwrap();
+
+        let month = TomlDirectory::new(dir.path(), Vault::default())
+            .load_month(&october, Access::ReadOnly)
+            .unwrap();
+        assert_eq!(month.operations.len(), 2);
+        assert!(fs::read_to_string(dir.path().join("10-2026.bak"))
+            .unwrap()
+            .contains("Lunch"));
+    }
+}
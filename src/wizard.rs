@@ -0,0 +1,291 @@
+use std::{
+    io::{self, BufRead, ErrorKind, Write},
+    ops::Not,
+    str::FromStr,
+};
+
+use porquinho::{
+    config::Config,
+    file::BookkeepingFile,
+    parser::{Operation, OperationType},
+    storage, BigDecimal, Error, Payee, PayeeIndex, Result,
+};
+
+/// How many of the most used descriptions are offered
+const SUGGESTIONS: usize = 5;
+
+/// Asks for the details of an operation one at a time, offering what's already
+/// in the books as suggestions
+pub struct Wizard<'a, R, W> {
+    input: R,
+    /// Where the questions go, which is stderr so that stdout only has results
+    output: W,
+    config: &'a Config,
+    payees: &'a PayeeIndex,
+}
+
+impl<'a, R: BufRead, W: Write> Wizard<'a, R, W> {
+    pub fn new(input: R, output: W, config: &'a Config, payees: &'a PayeeIndex) -> Self {
+        Self { input, output, config, payees }
+    }
+
+    /// The operation to add to `month`, or `None` if it isn't confirmed
+    ///
+    /// The day defaults to `today`, and `category`, if given, is used instead of
+    /// asking for one. Fails with `Error::InputEnded` if the input ends before the
+    /// operation is confirmed.
+    pub fn ask(
+        &mut self,
+        kind: OperationType,
+        month: &BookkeepingFile,
+        today: u8,
+        category: Option<String>,
+    ) -> Result<Option<Operation>> {
+        match self.ask_until_confirmed(kind, month, today, category) {
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => Err(Error::InputEnded),
+            result => result.map_err(Into::into),
+        }
+    }
+
+    fn ask_until_confirmed(
+        &mut self,
+        kind: OperationType,
+        month: &BookkeepingFile,
+        today: u8,
+        category: Option<String>,
+    ) -> io::Result<Option<Operation>> {
+        let amount = self.amount()?;
+        let description = self.description()?;
+        let day = self.day(month, today)?;
+        let category = match category {
+            Some(category) => Some(category),
+            None => self.category(&description)?,
+        };
+
+        let mut operation = Operation::new(day, kind, amount, description);
+        operation.category = category;
+
+        writeln!(
+            self.output,
+            "\nTo be added to {}:\n\n{}",
+            month.as_str(),
+            storage::record_text(&operation)
+        )?;
+
+        Ok(self.confirm("Add it?")?.then_some(operation))
+    }
+
+    fn amount(&mut self) -> io::Result<BigDecimal> {
+        loop {
+            let answer = self.prompt("Amount")?;
+            match BigDecimal::from_str(&answer) {
+                Ok(amount) => return Ok(amount),
+                Err(_) => writeln!(self.output, "'{answer}' is not an amount, e.g. 12.50")?,
+            }
+        }
+    }
+
+    fn description(&mut self) -> io::Result<String> {
+        let payees = self.payees.payees();
+        let usual: Vec<_> = payees
+            .iter()
+            .take(SUGGESTIONS)
+            .map(|payee| payee.canonical())
+            .collect();
+
+        let question = if usual.is_empty() {
+            "Description"
+        } else {
+            let numbered: Vec<_> = usual
+                .iter()
+                .enumerate()
+                .map(|(index, description)| format!("{}) {description}", index + 1))
+                .collect();
+            writeln!(self.output, "Used the most: {}", numbered.join("  "))?;
+
+            "Description, or the number of one above"
+        };
+
+        loop {
+            let answer = self.prompt(question)?;
+
+            let picked = answer
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| usual.get(number.checked_sub(1)?));
+            if let Some(description) = picked {
+                return Ok(description.to_string());
+            }
+
+            if answer.is_empty() {
+                continue;
+            }
+
+            if let Some(usual) = self.payees.suggest(&answer) {
+                if self.confirm(&format!("Did you mean \"{usual}\"?"))? {
+                    return Ok(usual.to_owned());
+                }
+            }
+
+            return Ok(answer);
+        }
+    }
+
+    fn day(&mut self, month: &BookkeepingFile, today: u8) -> io::Result<u8> {
+        loop {
+            let answer = self.prompt(&format!("Day [{today}]"))?;
+            if answer.is_empty() {
+                return Ok(today);
+            }
+
+            match answer.parse() {
+                Ok(day) if month.has_day(day) => return Ok(day),
+                _ => writeln!(self.output, "'{answer}' is not a day of {}", month.as_str())?,
+            }
+        }
+    }
+
+    /// Suggests the category the payee was last given, with `-` for no category
+    fn category(&mut self, description: &str) -> io::Result<Option<String>> {
+        let usual = self
+            .payees
+            .find(description)
+            .and_then(Payee::category)
+            .filter(|category| self.config.check_category(category).is_ok());
+
+        let known: Vec<_> = if self.config.categories.is_empty() {
+            self.payees.categories().into_iter().collect()
+        } else {
+            self.config.categories.iter().map(String::as_str).collect()
+        };
+
+        let mut question = String::from("Category");
+        if known.is_empty().not() {
+            question.push_str(&format!(" ({})", known.join(", ")));
+        }
+        question.push_str(&format!(", - for none [{}]", usual.unwrap_or("-")));
+
+        loop {
+            let category = match self.prompt(&question)?.as_str() {
+                "" => usual.map(str::to_owned),
+                "-" => None,
+                answer => Some(answer.to_owned()),
+            };
+
+            match category
+                .as_deref()
+                .map(|category| self.config.check_category(category))
+            {
+                Some(Err(err)) => writeln!(self.output, "{err}")?,
+                _ => return Ok(category),
+            }
+        }
+    }
+
+    fn confirm(&mut self, question: &str) -> io::Result<bool> {
+        loop {
+            match self
+                .prompt(&format!("{question} [Y/n]"))?
+                .to_lowercase()
+                .as_str()
+            {
+                "" | "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+
+    /// The trimmed answer to `question`, failing with `UnexpectedEof` once the input ends
+    fn prompt(&mut self, question: &str) -> io::Result<String> {
+        write!(self.output, "{question}: ")?;
+        self.output.flush()?;
+
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            writeln!(self.output)?;
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(answer.trim().to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use porquinho::{
+        config::Config,
+        file::BookkeepingFile,
+        parser::{Operation, OperationType},
+        BigDecimal, Error, PayeeIndex, Result,
+    };
+
+    use super::Wizard;
+
+    fn ask_with_category(
+        answers: &str,
+        config: &Config,
+        category: Option<&str>,
+    ) -> Result<Option<Operation>> {
+        let mut payees = PayeeIndex::default();
+        payees.add("Aluguel", Some("home"));
+        payees.add("iFood", Some("food"));
+        payees.add("iFood", None);
+
+        let october = BookkeepingFile::from_file_name("10-2026").unwrap();
+        let mut output = Vec::new();
+
+        Wizard::new(answers.as_bytes(), &mut output, config, &payees).ask(
+            OperationType::Withdraw,
+            &october,
+            19,
+            category.map(String::from),
+        )
+    }
+
+    fn ask(answers: &str, config: &Config) -> Option<Operation> {
+        ask_with_category(answers, config, None).unwrap()
+    }
+
+    #[test]
+    fn builds_the_operation_from_the_answers() {
+        // A bad amount, a misspelled description, today and its usual category
+        let operation = ask("lots\n1200\nAlugel\n\n\n\ny\n", &Config::default()).unwrap();
+        assert_eq!(operation.amount, BigDecimal::from(1200));
+        assert_eq!(operation.description, "Aluguel");
+        assert_eq!(operation.day, 19);
+        assert_eq!(operation.category.as_deref(), Some("home"));
+
+        // The most used description, picked by number, on another day without a category
+        let operation = ask("35.90\n1\n32\n7\n-\n\n", &Config::default()).unwrap();
+        assert_eq!(operation.description, "iFood");
+        assert_eq!(operation.day, 7);
+        assert_eq!(operation.category, None);
+    }
+
+    #[test]
+    fn only_accepts_known_categories() {
+        let config = Config {
+            categories: vec!["fun".into()],
+            ..Config::default()
+        };
+
+        let operation = ask("10\nCinema\n\nsnacks\nfun\n\n", &config).unwrap();
+        assert_eq!(operation.category.as_deref(), Some("fun"));
+
+        // A category given up front isn't asked for
+        let operation = ask_with_category("10\nCinema\n\n\n", &config, Some("fun"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(operation.category.as_deref(), Some("fun"));
+    }
+
+    #[test]
+    fn adds_nothing_unless_confirmed() {
+        assert!(ask("10\nCinema\n\n\nn\n", &Config::default()).is_none());
+        assert!(matches!(
+            ask_with_category("10\nCine", &Config::default(), None),
+            Err(Error::InputEnded)
+        ));
+    }
+}